@@ -0,0 +1,473 @@
+//! A copy of [`Object`] as it was before it had a serializer of its own, when it
+//! put the value of an object next to its `Z1K1` with `#[serde(flatten)]`.
+//!
+//! The model types in `full_eval.json` are copied here too, so that their fields,
+//! the elements of their lists and the types of their lists all go through this
+//! `Object` as well, and every level of the object is flattened as it used to be.
+//! Strings and references are (de)serialized by the model crate, as they have no
+//! objects inside them.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::slice;
+
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, Error, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wikifunctions::label;
+use wikifunctions::model::form::{self, Form};
+use wikifunctions::model::{Reference, ZString};
+
+pub mod object {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Object, ZObject};
+
+    pub fn serialize<T: ZObject + Serialize, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Object {
+            ty: Default::default(),
+            value,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, T: ZObject + Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let obj: Object<T> = Object::deserialize(deserializer)?;
+        Ok(obj.value)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Object<T: ZObject> {
+    pub ty: T::ZType,
+    pub value: T,
+}
+
+impl<T: ZObject> Object<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            ty: Default::default(),
+            value,
+        }
+    }
+}
+
+/// How an object is laid out when it has a `Z1K1`.
+#[derive(Serialize, Deserialize)]
+struct ObjectRepr<Ty, T> {
+    #[serde(rename = "Z1K1")]
+    ty: Ty,
+    #[serde(flatten)]
+    value: T,
+}
+
+impl<T: ZObject + Serialize> Serialize for Object<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if T::CANONICAL_BARE && form::current() == Form::Canonical {
+            return self.value.serialize(serializer);
+        }
+        ObjectRepr {
+            ty: &self.ty,
+            value: &self.value,
+        }
+        .serialize(serializer)
+    }
+}
+
+struct ObjectVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ZObject + Deserialize<'de>> Visitor<'de> for ObjectVisitor<T> {
+    type Value = Object<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a ZObject")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Object<T>, E> {
+        T::deserialize(v.into_deserializer()).map(Object::new)
+    }
+
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Object<T>, E> {
+        T::deserialize(BorrowedStrDeserializer::new(v)).map(Object::new)
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Object<T>, E> {
+        T::deserialize(v.into_deserializer()).map(Object::new)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Object<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        T::deserialize(SeqAccessDeserializer::new(seq)).map(Object::new)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Object<T>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let ObjectRepr { ty, value } = ObjectRepr::deserialize(MapAccessDeserializer::new(map))?;
+        Ok(Object { ty, value })
+    }
+}
+
+impl<'de, T: ZObject + Deserialize<'de>> Deserialize<'de> for Object<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ObjectVisitor(PhantomData))
+    }
+}
+
+pub trait ZObject {
+    type ZType: ZType;
+    const CANONICAL_BARE: bool = false;
+}
+
+impl<T: ZObject> ZObject for &'_ T {
+    type ZType = T::ZType;
+    const CANONICAL_BARE: bool = T::CANONICAL_BARE;
+}
+
+pub trait ZType: DeserializeOwned + Serialize + fmt::Debug + Default {
+    type Value: DeserializeOwned + Serialize + fmt::Debug + Default;
+}
+
+impl<T> ZType for Object<T>
+where
+    T: ZObject + DeserializeOwned + Serialize + fmt::Debug + Default,
+{
+    type Value = Self;
+}
+
+macro_rules! labels {
+    ($($label:ident)*) => {
+        $(impl ZType for label::$label {
+            type Value = Object<Reference<label::$label>>;
+        })*
+    };
+}
+
+labels!(Z6 Z7 Z8 Z9 Z11 Z12 Z14 Z16 Z17 Z20 Z61 Z881);
+
+impl ZObject for ZString {
+    type ZType = label::Z6;
+    const CANONICAL_BARE: bool = true;
+}
+
+impl<Id> ZObject for Reference<Id> {
+    type ZType = label::Z9;
+    const CANONICAL_BARE: bool = true;
+}
+
+/// A Z881/typed list.
+#[derive(Debug)]
+pub struct TypedList<T: ZObject> {
+    pub inner: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier)]
+enum ListKey {
+    Z1K1,
+    K1,
+    K2,
+}
+
+struct TypedListVisitor<T> {
+    expecting_type: bool,
+    ph: PhantomData<T>,
+}
+
+impl<'de, T: ZObject + Deserialize<'de>> DeserializeSeed<'de> for TypedListVisitor<T> {
+    type Value = Vec<T>;
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, T: ZObject + Deserialize<'de>> Visitor<'de> for TypedListVisitor<T> {
+    type Value = Vec<T>;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a map")
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Vec<T>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut has_type = false;
+        let mut head = None;
+        let mut tail = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ListKey::Z1K1 if has_type => return Err(A::Error::duplicate_field("Z1K1")),
+                ListKey::Z1K1 => {
+                    map.next_value::<<TypedList<T> as ZObject>::ZType>()?;
+                    has_type = true;
+                }
+                ListKey::K1 if head.is_some() => return Err(A::Error::duplicate_field("K1")),
+                ListKey::K1 => head = Some(map.next_value::<Object<T>>()?.value),
+                ListKey::K2 if tail.is_some() => return Err(A::Error::duplicate_field("K2")),
+                ListKey::K2 => {
+                    tail = Some(map.next_value_seed(TypedListVisitor {
+                        expecting_type: true,
+                        ph: PhantomData,
+                    })?)
+                }
+            }
+        }
+        if self.expecting_type && !has_type {
+            return Err(A::Error::missing_field("Z1K1"));
+        }
+        match (head, tail) {
+            (Some(head), Some(mut entries)) => {
+                entries.push(head);
+                Ok(entries)
+            }
+            (None, None) => Ok(Vec::new()),
+            (Some(_), None) => Err(A::Error::missing_field("K2")),
+            (None, Some(_)) => Err(A::Error::missing_field("K1")),
+        }
+    }
+}
+
+struct ListVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ZObject + Deserialize<'de>> Visitor<'de> for ListVisitor<T> {
+    type Value = TypedList<T>;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a typed list")
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<TypedList<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        if seq.next_element::<<T::ZType as ZType>::Value>()?.is_none() {
+            return Err(A::Error::custom("expected the element type of the list"));
+        }
+        let mut inner = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element::<Object<T>>()? {
+            inner.push(v.value);
+        }
+        Ok(TypedList { inner })
+    }
+    fn visit_map<A>(self, map: A) -> Result<TypedList<T>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut inner = TypedListVisitor {
+            expecting_type: false,
+            ph: PhantomData,
+        }
+        .visit_map(map)?;
+        inner.reverse();
+        Ok(TypedList { inner })
+    }
+}
+
+impl<'de, T: ZObject + Deserialize<'de>> Deserialize<'de> for TypedList<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ListVisitor(PhantomData))
+    }
+}
+
+struct TypedListSerializeImpl<'a, T> {
+    iter: slice::Iter<'a, T>,
+}
+
+impl<T: ZObject> ZObject for TypedListSerializeImpl<'_, T> {
+    type ZType = <TypedList<T> as ZObject>::ZType;
+}
+
+impl<T: ZObject + Serialize> Serialize for TypedListSerializeImpl<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut iter = self.iter.clone();
+        if let Some(val) = iter.next() {
+            let mut serializer = serializer.serialize_map(Some(2))?;
+            serializer.serialize_entry("K1", &Object::new(val))?;
+            serializer.serialize_entry("K2", &Object::new(TypedListSerializeImpl { iter }))?;
+            serializer.end()
+        } else {
+            serializer.serialize_map(Some(0))?.end()
+        }
+    }
+}
+
+impl<T: ZObject + Serialize> Serialize for TypedList<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if form::current() == Form::Canonical {
+            let mut seq = serializer.serialize_seq(Some(self.inner.len() + 1))?;
+            seq.serialize_element(&<T::ZType as ZType>::Value::default())?;
+            for val in &self.inner {
+                seq.serialize_element(&Object::new(val))?;
+            }
+            return seq.end();
+        }
+        TypedListSerializeImpl {
+            iter: self.inner.iter(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T: ZObject> ZObject for TypedList<T> {
+    type ZType =
+        Object<FunctionCall<Reference<label::Z881>, TypeListArgs<<T::ZType as ZType>::Value>>>;
+    const CANONICAL_BARE: bool = true;
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TypeListArgs<Type> {
+    #[serde(rename = "Z881K1")]
+    pub ty: Type,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct FunctionCall<Func: ZObject, Args> {
+    #[serde(rename = "Z7K1", with = "object")]
+    #[serde(bound(serialize = "Func: Serialize", deserialize = "Func: Deserialize<'de>"))]
+    pub function: Func,
+    #[serde(flatten)]
+    pub args: Args,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MonolingualText {
+    #[serde(rename = "Z11K1", with = "object")]
+    pub language: Reference,
+    #[serde(rename = "Z11K2", with = "object")]
+    pub text: ZString,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultilingualText {
+    #[serde(rename = "Z12K1", with = "object")]
+    pub texts: TypedList<MonolingualText>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Argument<Type: ZObject> {
+    #[serde(rename = "Z17K1", with = "object")]
+    #[serde(bound(serialize = "Type: Serialize", deserialize = "Type: Deserialize<'de>"))]
+    pub ty: Type,
+    #[serde(rename = "Z17K2", with = "object")]
+    pub key: ZString,
+    #[serde(rename = "Z17K3", with = "object")]
+    pub label: MultilingualText,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound(
+    serialize = "Type: Serialize, ReturnType: Serialize, TestCase: Serialize, \
+                           Implementation: Serialize, Identity: Serialize"
+))]
+#[serde(
+    bound(deserialize = "Type: Deserialize<'de>, ReturnType: Deserialize<'de>, \
+                             TestCase: Deserialize<'de>, Implementation: Deserialize<'de>, \
+                             Identity: Deserialize<'de>")
+)]
+pub struct Function<
+    Type: ZObject,
+    ReturnType: ZObject,
+    TestCase: ZObject,
+    Implementation: ZObject,
+    Identity: ZObject,
+> {
+    #[serde(rename = "Z8K1", with = "object")]
+    pub arguments: TypedList<Argument<Type>>,
+    #[serde(rename = "Z8K2", with = "object")]
+    pub return_type: ReturnType,
+    #[serde(rename = "Z8K3", with = "object")]
+    pub test_cases: TypedList<TestCase>,
+    #[serde(rename = "Z8K4", with = "object")]
+    pub implementations: TypedList<Implementation>,
+    #[serde(rename = "Z8K5", with = "object")]
+    pub identity: Identity,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Implementation<Fn: ZObject> {
+    #[serde(rename = "Z14K1", with = "object")]
+    #[serde(bound(serialize = "Fn: Serialize", deserialize = "Fn: Deserialize<'de>"))]
+    pub function: Fn,
+    #[serde(rename = "Z14K3", with = "object")]
+    pub code: Code,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProgrammingLanguage {
+    #[serde(rename = "Z61K1", with = "object")]
+    pub code: ZString,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Code {
+    #[serde(rename = "Z16K1", with = "object")]
+    pub language: ProgrammingLanguage,
+    #[serde(rename = "Z16K2", with = "object")]
+    pub code: ZString,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Tester {}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Args {
+    #[serde(rename = "Z1000K1", with = "object")]
+    pub left: ZString,
+    #[serde(rename = "Z1000K2", with = "object")]
+    pub right: ZString,
+}
+
+macro_rules! impl_zobject {
+    ($( $(@[$($tt:tt)*])? $ty:ty = $label:ident ),*$(,)?) => {
+        $(impl $(<$($tt)*>)? ZObject for $ty {
+            type ZType = Object<Reference<label::$label>>;
+        })*
+    };
+}
+
+impl_zobject! {
+    @[Func: ZObject, Args] FunctionCall<Func, Args> = Z7,
+    @[Type: ZObject, ReturnType: ZObject, TestCase: ZObject, Implementation: ZObject, Identity: ZObject]
+        Function<Type, ReturnType, TestCase, Implementation, Identity> = Z8,
+    MonolingualText = Z11,
+    MultilingualText = Z12,
+    @[Fn: ZObject] Implementation<Fn> = Z14,
+    Code = Z16,
+    @[Type: ZObject] Argument<Type> = Z17,
+    Tester = Z20,
+    ProgrammingLanguage = Z61,
+}
+
+pub type Call = FunctionCall<
+    Function<Reference, Reference, Tester, Implementation<Reference>, Reference>,
+    Args,
+>;
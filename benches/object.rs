@@ -0,0 +1,73 @@
+//! Benchmarks (de)serializing `test_data/full_eval.json` as typed objects.
+//!
+//! `flatten` is the previous implementation of [`Object`], which used
+//! `#[serde(flatten)]` to put the value of an object next to its `Z1K1`, with
+//! the model types copied so that it is used at every level of the object.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde::{Deserialize, Serialize};
+use wikifunctions::label;
+use wikifunctions::model::{
+    object, Function, FunctionCall, Implementation, Object, Reference, ZObject, ZString, ZValue,
+};
+
+mod flatten;
+
+const FULL_EVAL: &str = include_str!("../test_data/full_eval.json");
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Tester {}
+
+impl ZObject for Tester {
+    type ZType = Object<Reference<label::Z20>>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Args {
+    #[serde(rename = "Z1000K1", with = "object")]
+    left: ZString,
+    #[serde(rename = "Z1000K2", with = "object")]
+    right: ZString,
+}
+
+type Call = FunctionCall<
+    Function<Reference, Reference, Tester, Implementation<Reference>, Reference>,
+    Args,
+>;
+
+fn deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deserialize full_eval.json");
+    group.bench_function("object", |b| {
+        b.iter(|| serde_json::from_str::<Object<Call>>(black_box(FULL_EVAL)).unwrap())
+    });
+    group.bench_function("flatten", |b| {
+        b.iter(|| {
+            serde_json::from_str::<flatten::Object<flatten::Call>>(black_box(FULL_EVAL)).unwrap()
+        })
+    });
+    group.bench_function("dynamic", |b| {
+        b.iter(|| serde_json::from_str::<ZValue>(black_box(FULL_EVAL)).unwrap())
+    });
+    group.finish();
+}
+
+fn serialize(c: &mut Criterion) {
+    let call: Object<Call> = serde_json::from_str(FULL_EVAL).unwrap();
+    let flattened: flatten::Object<flatten::Call> = serde_json::from_str(FULL_EVAL).unwrap();
+    let dynamic: ZValue = serde_json::from_str(FULL_EVAL).unwrap();
+
+    let mut group = c.benchmark_group("serialize full_eval.json");
+    group.bench_function("object", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&call)).unwrap())
+    });
+    group.bench_function("flatten", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&flattened)).unwrap())
+    });
+    group.bench_function("dynamic", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&dynamic)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deserialize, serialize);
+criterion_main!(benches);
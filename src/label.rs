@@ -1,23 +1,53 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use crate::model::object::ZType;
+use crate::model::{Object, Reference};
+
 pub trait ZLabel {
     const LABEL: &'static str;
+    /// The number of the ZID, such as 6 for `Z6`.
+    const ID: u32 = zid_number(Self::LABEL);
     const VAL: Self;
 }
 
+/// The number of `label`, a ZID such as `Z6`.
+const fn zid_number(label: &str) -> u32 {
+    let bytes = label.as_bytes();
+    assert!(bytes.len() > 1 && bytes[0] == b'Z', "labels are ZIDs");
+    let mut id = 0;
+    let mut i = 1;
+    while i < bytes.len() {
+        assert!(bytes[i].is_ascii_digit(), "labels are ZIDs");
+        id = id * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    id
+}
+
 macro_rules! impl_zlabel {
     ($($ident:ident)*) => {
         $(
             #[derive(Debug, Default)]
             pub struct $ident;
 
+            impl ZLabel for $ident {
+                const LABEL: &'static str = stringify!($ident);
+                const VAL: Self = $ident;
+            }
+
+            impl ZType for $ident {
+                type Value = Object<Reference<$ident>>;
+            }
+
             impl<'de> Deserialize<'de> for $ident {
                 fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                 where
                     D: serde::Deserializer<'de>,
                 {
                     let label = String::deserialize(deserializer)?;
-                    if label == stringify!($ident) {
+                    if label == Self::LABEL {
                         Ok($ident)
                     } else {
                         Err(serde::de::Error::custom(format!(
@@ -33,11 +63,93 @@ macro_rules! impl_zlabel {
                 where
                     S: serde::Serializer,
                 {
-                    serializer.serialize_str(stringify!($ident))
+                    serializer.serialize_str(Self::LABEL)
                 }
             }
         )*
     };
 }
 
-impl_zlabel!(Z6 Z7 Z9 Z11 Z12 Z14 Z16 Z17 Z22 Z24 Z60 Z61 Z881);
+impl_zlabel!(
+    Z1 Z2 Z3 Z4 Z5 Z6 Z7 Z8 Z9 Z11 Z12 Z14 Z16 Z17 Z20 Z21 Z22 Z24 Z31 Z32 Z40 Z41 Z42 Z46 Z60
+    Z61 Z64 Z881 Z882 Z883
+);
+
+/// The label of any ZID, such as `Z<10000>` for `Z10000`, which is what
+/// `#[derive(ZObject)]` uses for the type of an object. It (de)serializes as the
+/// bare ZID, like the named labels.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct Z<const ID: u32>;
+
+/// The longest ZID that a `u32` can have, `Z4294967295`.
+const MAX_ZID_LEN: usize = 11;
+
+impl<const ID: u32> Z<ID> {
+    /// The ZID, right-aligned.
+    const ZID: [u8; MAX_ZID_LEN] = {
+        let mut zid = [b'Z'; MAX_ZID_LEN];
+        let mut n = ID;
+        let mut i = MAX_ZID_LEN;
+        loop {
+            i -= 1;
+            zid[i] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        zid
+    };
+    /// Where the ZID starts in `ZID`.
+    const START: usize = {
+        let mut start = MAX_ZID_LEN - 1;
+        while Self::ZID[start - 1] != b'Z' {
+            start -= 1;
+        }
+        start - 1
+    };
+}
+
+impl<const ID: u32> ZLabel for Z<ID> {
+    const LABEL: &'static str = match std::str::from_utf8(Self::ZID.split_at(Self::START).1) {
+        Ok(label) => label,
+        Err(_) => unreachable!(),
+    };
+    const ID: u32 = ID;
+    const VAL: Self = Z;
+}
+
+impl<const ID: u32> ZType for Z<ID> {
+    type Value = Object<Reference<Z<ID>>>;
+}
+
+impl<const ID: u32> fmt::Debug for Z<ID> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::LABEL)
+    }
+}
+
+impl<'de, const ID: u32> Deserialize<'de> for Z<ID> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let label = String::deserialize(deserializer)?;
+        if label == Self::LABEL {
+            Ok(Z)
+        } else {
+            Err(serde::de::Error::custom(format!(
+                "expected label Z{ID}, got {label}"
+            )))
+        }
+    }
+}
+
+impl<const ID: u32> Serialize for Z<ID> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(Self::LABEL)
+    }
+}
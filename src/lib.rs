@@ -1,6 +1,15 @@
-mod label;
+// lets `#[derive(ZObject)]` refer to this crate as `::wikifunctions`.
+extern crate self as wikifunctions;
+
+pub mod label;
 
 pub mod model;
 
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::model::object::de::Key;
+    pub use serde;
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,65 +1,205 @@
+use std::marker::PhantomData;
+
 use serde::{Deserialize, Serialize};
 
 use crate::label;
 
+pub use self::boolean::Boolean;
+pub use self::error::ZError;
 use self::list::TypedList;
+pub use self::number::{Float64, Integer, NaturalNumber, Sign, SpecialValue};
+use self::object::ZType;
 pub use self::object::{Object, ZObject};
+pub use self::value::ZValue;
+pub use wikifunctions_derive::ZObject;
+
+mod scalar;
 
+pub mod boolean;
+pub mod error;
+pub mod form;
 pub mod list;
+pub mod number;
 pub mod object;
+pub mod value;
+
+/// A reference to Z24/void, which stands for the lack of a value, such as the
+/// result of a failed evaluation.
+pub type Void = Reference<label::Z24>;
 
-pub type ZUnit = Reference<label::Z24>;
+/// A Z21/unit, the type of values that carry no information, written as an object
+/// with no keys. Rust's `()` is a unit.
+#[derive(ZObject, Debug, Default)]
+#[zobject(Z21)]
+pub struct ZUnit {}
 
 /// a Z9/reference
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct Reference<Id = String> {
-    #[serde(rename = "Z9K1")]
     pub id: Id,
 }
 
 impl<Id> ZObject for Reference<Id> {
     type ZType = label::Z9;
+    const CANONICAL_BARE: bool = true;
+}
+
+/// A Z9/reference to an object of type `Type`. It is written like any other reference,
+/// but a list of them is typed by what they refer to, so a list of references to
+/// Z46/deserialisers is written as `["Z46", ...]` in canonical form.
+#[derive(Debug, Default)]
+pub struct TypedReference<Type> {
+    pub id: String,
+    pub ty: PhantomData<Type>,
+}
+
+/// The `Z1K1` of a [`TypedReference`], which is a Z9.
+#[derive(Debug, Default)]
+pub struct TypedReferenceType<Type>(PhantomData<Type>);
+
+impl<Type: ZType> ZType for TypedReferenceType<Type> {
+    type Value = Object<Reference<Type>>;
+}
+
+impl<Type: ZType> ZObject for TypedReference<Type> {
+    type ZType = TypedReferenceType<Type>;
+    const CANONICAL_BARE: bool = true;
 }
 
 /// A Z60/natural language.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z60)]
 pub struct NaturalLanguage {
-    #[serde(rename = "Z60K1", with = "object")]
+    #[zobject(K1)]
     pub code: ZString,
-    #[serde(rename = "Z60K2", with = "object")]
+    #[zobject(K2)]
     pub code_aliases: TypedList<ZString>,
 }
 
 /// A Z11/monolingual text.
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Persistent objects refer to the language of their labels rather than include
+/// it, so `language` is a [`Reference`] and not a [`NaturalLanguage`], and the text
+/// is `Z11K2` as in the definition of Z11, not `Z12K1`.
+#[derive(ZObject, Debug)]
+#[zobject(Z11)]
 pub struct MonolingualText {
-    #[serde(rename = "Z11K1", with = "object")]
-    pub language: NaturalLanguage,
-    #[serde(rename = "Z12K1", with = "object")]
+    /// A reference to the Z60/natural language of the text, such as `Z1002` for English.
+    #[zobject(K1)]
+    pub language: Reference,
+    #[zobject(K2)]
     pub text: ZString,
 }
 
-/// A Z12/monolingual text.
-#[derive(Serialize, Deserialize, Debug)]
+/// A Z12/multilingual text.
+#[derive(ZObject, Debug)]
+#[zobject(Z12)]
 pub struct MultilingualText {
-    #[serde(rename = "Z12K1", with = "object")]
+    #[zobject(K1)]
     pub texts: TypedList<MonolingualText>,
 }
 
+/// A Z31/monolingual stringset.
+#[derive(ZObject, Debug)]
+#[zobject(Z31)]
+pub struct MonolingualStringset {
+    /// A reference to the Z60/natural language of the strings.
+    #[zobject(K1)]
+    pub language: Reference,
+    #[zobject(K2)]
+    pub strings: TypedList<ZString>,
+}
+
+/// A Z32/multilingual stringset.
+#[derive(ZObject, Debug)]
+#[zobject(Z32)]
+pub struct MultilingualStringset {
+    #[zobject(K1)]
+    pub stringsets: TypedList<MonolingualStringset>,
+}
+
+/// A Z2/persistent object, which wraps every object stored on the wiki.
+#[derive(ZObject, Debug)]
+#[zobject(Z2)]
+pub struct Persistent<T: ZObject> {
+    /// The ZID of the object.
+    #[zobject(K1)]
+    pub id: ZString,
+    #[zobject(K2)]
+    pub value: T,
+    #[zobject(K3)]
+    pub labels: MultilingualText,
+    #[zobject(K4)]
+    pub aliases: MultilingualStringset,
+    /// A short description of the object. Older objects may not have one.
+    #[zobject(K5, optional)]
+    pub description: Option<MultilingualText>,
+}
+
+/// A Z3/key declaration.
+#[derive(ZObject, Debug)]
+#[zobject(Z3)]
+pub struct KeyDeclaration<Type: ZObject = Reference> {
+    /// The type of the value at this key.
+    #[zobject(K1)]
+    pub ty: Type,
+    /// The key itself, such as `Z40K1`.
+    #[zobject(K2)]
+    pub id: ZString,
+    #[zobject(K3)]
+    pub label: MultilingualText,
+    /// Whether this key identifies the object. Usually left out.
+    #[zobject(K4, optional)]
+    pub is_identity: Option<Boolean>,
+}
+
+/// A Z4/type definition.
+///
+/// Functions and arguments can refer to a type definition either inline, as
+/// `Argument<TypeDefinition>`, or through a `Reference` to it.
+#[derive(ZObject, Debug)]
+#[zobject(Z4)]
+pub struct TypeDefinition<Identity: ZObject = Reference, KeyType: ZObject = Reference> {
+    /// The type itself, usually a reference to the ZID of its Z2/persistent object.
+    #[zobject(K1)]
+    pub identity: Identity,
+    #[zobject(K2)]
+    pub keys: TypedList<KeyDeclaration<KeyType>>,
+    /// A reference to the Z8/function that validates objects of this type.
+    #[zobject(K3)]
+    pub validator: Reference,
+    /// A reference to the Z8/function that compares two objects of this type.
+    #[zobject(K4, optional)]
+    pub equality: Option<Reference>,
+    /// A reference to the Z8/function that displays objects of this type.
+    #[zobject(K5, optional)]
+    pub renderer: Option<Reference>,
+    /// A reference to the Z8/function that reads objects of this type from a string.
+    #[zobject(K6, optional)]
+    pub parser: Option<Reference>,
+    /// The Z46/deserialisers that convert objects of this type into code.
+    #[zobject(K7, optional)]
+    pub deserialisers: Option<TypedList<TypedReference<label::Z46>>>,
+    /// The Z64/serialisers that convert values in code into objects of this type.
+    #[zobject(K8, optional)]
+    pub serialisers: Option<TypedList<TypedReference<label::Z64>>>,
+}
+
 /// A Z17/argument declaration.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z17)]
 pub struct Argument<Type: ZObject> {
-    #[serde(rename = "Z17K1", with = "object")]
-    #[serde(bound(serialize = "Type: Serialize", deserialize = "Type: Deserialize<'de>"))]
+    #[zobject(K1)]
     pub ty: Type,
-    #[serde(rename = "Z17K2", with = "object")]
+    #[zobject(K2)]
     pub key: ZString,
-    #[serde(rename = "Z17K3", with = "object")]
+    #[zobject(K3)]
     pub label: MultilingualText,
 }
 
 /// A Z8/function.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z8)]
 pub struct Function<
     Type: ZObject,
     ReturnType: ZObject,
@@ -67,54 +207,32 @@ pub struct Function<
     Implementation: ZObject,
     Identity: ZObject,
 > {
-    #[serde(rename = "Z8K1", with = "object")]
-    #[serde(bound(serialize = "Type: Serialize", deserialize = "Type: Deserialize<'de>"))]
+    #[zobject(K1)]
     pub arguments: TypedList<Argument<Type>>,
-
-    #[serde(rename = "Z8K2", with = "object")]
-    #[serde(bound(
-        serialize = "ReturnType: Serialize",
-        deserialize = "ReturnType: Deserialize<'de>"
-    ))]
+    #[zobject(K2)]
     pub return_type: ReturnType,
-
-    #[serde(rename = "Z8K3", with = "object")]
-    #[serde(bound(
-        serialize = "TestCase: Serialize",
-        deserialize = "TestCase: Deserialize<'de>"
-    ))]
+    #[zobject(K3)]
     pub test_cases: TypedList<TestCase>,
-
-    #[serde(rename = "Z8K4", with = "object")]
-    #[serde(bound(
-        serialize = "Implementation: Serialize",
-        deserialize = "Implementation: Deserialize<'de>"
-    ))]
+    #[zobject(K4)]
     pub implementations: TypedList<Implementation>,
-
-    #[serde(rename = "Z8K5", with = "object")]
-    #[serde(bound(
-        serialize = "Identity: Serialize",
-        deserialize = "Identity: Deserialize<'de>"
-    ))]
+    #[zobject(K5)]
     pub identity: Identity,
 }
 
 /// An Z14/implementation
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z14)]
 pub struct Implementation<Fn: ZObject> {
     /// The function that this implementation is for
-    #[serde(rename = "Z14K1", with = "object")]
-    #[serde(bound(serialize = "Fn: Serialize", deserialize = "Fn: Deserialize<'de>"))]
+    #[zobject(K1)]
     pub function: Fn,
-    #[serde(rename = "Z14K3", with = "object")]
+    #[zobject(K3)]
     pub code: Code,
 }
 
 /// A Z6/string
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct ZString {
-    #[serde(rename = "Z6K1")]
     pub value: String,
 }
 
@@ -129,6 +247,7 @@ impl From<&'_ str> for ZString {
 
 impl ZObject for ZString {
     type ZType = label::Z6;
+    const CANONICAL_BARE: bool = true;
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -137,6 +256,22 @@ pub struct TypeListArgs<Type> {
     pub ty: Type,
 }
 
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TypePairArgs<First, Second> {
+    #[serde(rename = "Z882K1")]
+    pub first: First,
+    #[serde(rename = "Z882K2")]
+    pub second: Second,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TypeMapArgs<Key, Value> {
+    #[serde(rename = "Z883K1")]
+    pub key: Key,
+    #[serde(rename = "Z883K2")]
+    pub value: Value,
+}
+
 /// a Z7/function call
 #[derive(Deserialize, Serialize, Debug, Default)]
 pub struct FunctionCall<Func: ZObject, Args> {
@@ -147,51 +282,83 @@ pub struct FunctionCall<Func: ZObject, Args> {
     pub args: Args,
 }
 
+/// A Z882/typed pair, whose type is a call to Z882 with the types of its elements.
+#[derive(ZObject, Debug)]
+#[zobject(
+    Z882,
+    ztype = Object<
+        FunctionCall<
+            Reference<label::Z882>,
+            TypePairArgs<<First::ZType as ZType>::Value, <Second::ZType as ZType>::Value>,
+        >,
+    >
+)]
+pub struct TypedPair<First: ZObject, Second: ZObject> {
+    #[zobject(rename = "K1")]
+    pub first: First,
+    #[zobject(rename = "K2")]
+    pub second: Second,
+}
+
+impl<First: ZObject, Second: ZObject> From<(First, Second)> for TypedPair<First, Second> {
+    fn from((first, second): (First, Second)) -> Self {
+        TypedPair { first, second }
+    }
+}
+
+/// A Z883/typed map, which is a list of Z882/typed pairs of keys and values.
+#[derive(ZObject, Debug)]
+#[zobject(
+    Z883,
+    ztype = Object<
+        FunctionCall<
+            Reference<label::Z883>,
+            TypeMapArgs<<Key::ZType as ZType>::Value, <Value::ZType as ZType>::Value>,
+        >,
+    >
+)]
+pub struct TypedMap<Key: ZObject, Value: ZObject> {
+    #[zobject(rename = "K1")]
+    pub entries: TypedList<TypedPair<Key, Value>>,
+}
+
+impl<Key: ZObject, Value: ZObject> FromIterator<(Key, Value)> for TypedMap<Key, Value> {
+    fn from_iter<I: IntoIterator<Item = (Key, Value)>>(iter: I) -> Self {
+        let inner = iter.into_iter().map(TypedPair::from).collect();
+        TypedMap {
+            entries: TypedList { inner },
+        }
+    }
+}
+
 /// Represents a Z22/Pair.
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z22)]
 pub struct Pair<A: ZObject, B: ZObject> {
-    #[serde(rename = "Z22K1", with = "object")]
-    #[serde(bound(serialize = "A: Serialize", deserialize = "A: Deserialize<'de>"))]
+    #[zobject(K1)]
     pub left: A,
-    #[serde(rename = "Z22K2", with = "object")]
-    #[serde(bound(serialize = "B: Serialize", deserialize = "B: Deserialize<'de>"))]
+    #[zobject(K2)]
     pub right: B,
 }
 
 /// Represents a Z61/Programming language.
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z61)]
 pub struct ProgrammingLanguage {
-    #[serde(rename = "Z61K1", with = "object")]
+    #[zobject(K1)]
     pub code: ZString,
 }
 
 /// Rperesents a Z16/Code.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(ZObject, Debug)]
+#[zobject(Z16)]
 pub struct Code {
-    #[serde(rename = "Z16K1", with = "object")]
+    #[zobject(K1)]
     pub language: ProgrammingLanguage,
-    #[serde(rename = "Z16K2", with = "object")]
+    #[zobject(K2)]
     pub code: ZString,
 }
 
-/// Implements `ZObject` for types. Should not be used for `ZString` and `Reference`,
-/// as those types have special ZTypes (not wrapped with `Object<Reference<>>`)
-macro_rules! impl_zobject {
-    ($( $(@[$($tt:tt)*])? $ty:ty = $label:ident ),*$(,)?) => {
-        $(impl $(<$($tt)*>)? ZObject for $ty {
-            type ZType = Object<Reference<label::$label>>;
-        })*
-    };
-}
-
-impl_zobject! {
-    @[Func: ZObject, Args] FunctionCall<Func, Args> = Z7,
-    MonolingualText = Z11,
-    MultilingualText = Z12,
-    @[Fn: ZObject] Implementation<Fn> = Z14,
-    Code = Z16,
-    @[Type: ZObject] Argument<Type> = Z17,
-    @[A: ZObject, B: ZObject] Pair<A, B> = Z22,
-    NaturalLanguage = Z60,
-    ProgrammingLanguage = Z61,
+impl<Func: ZObject, Args> ZObject for FunctionCall<Func, Args> {
+    type ZType = Object<Reference<label::Z7>>;
 }
@@ -0,0 +1,148 @@
+//! (de)serialization for booleans, which are Z40 objects wrapping a reference to
+//! Z41/true or Z42/false, but are commonly written as just the reference.
+
+use std::fmt;
+
+use serde::de::{Error, MapAccess, Unexpected, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::object::de::Key;
+use super::object::ZType;
+use super::{Object, Reference, ZObject, ZValue};
+use crate::label;
+
+/// A Z40/boolean.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Boolean {
+    pub value: bool,
+}
+
+impl Boolean {
+    /// The ZID that this boolean refers to, `Z41` for true and `Z42` for false.
+    pub fn zid(self) -> &'static str {
+        if self.value {
+            "Z41"
+        } else {
+            "Z42"
+        }
+    }
+
+    fn from_zid<E: Error>(zid: &str) -> Result<Boolean, E> {
+        match zid {
+            "Z41" => Ok(Boolean { value: true }),
+            "Z42" => Ok(Boolean { value: false }),
+            _ => Err(E::invalid_value(Unexpected::Str(zid), &"Z41 or Z42")),
+        }
+    }
+}
+
+impl From<bool> for Boolean {
+    fn from(value: bool) -> Self {
+        Boolean { value }
+    }
+}
+
+impl From<Boolean> for bool {
+    fn from(value: Boolean) -> Self {
+        value.value
+    }
+}
+
+impl ZObject for Boolean {
+    type ZType = BooleanType;
+}
+
+/// The `Z1K1` of a boolean. It is written as a Z40, but a Z9 is accepted as well,
+/// since booleans are usually just references to Z41 or Z42.
+#[derive(Debug, Default)]
+pub struct BooleanType;
+
+impl ZType for BooleanType {
+    type Value = Object<Reference<label::Z40>>;
+}
+
+impl Serialize for BooleanType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Object::<Reference<label::Z40>>::default().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BooleanType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match ZValue::deserialize(deserializer)? {
+            ZValue::Reference(id) if id == "Z40" || id == "Z9" => Ok(BooleanType),
+            other => Err(D::Error::custom(format_args!(
+                "expected the type of a boolean, got {other:?}"
+            ))),
+        }
+    }
+}
+
+impl Serialize for Boolean {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("Z40K1", &Object::new(Reference { id: self.zid() }))?;
+        map.end()
+    }
+}
+
+struct BooleanVisitor;
+
+impl<'de> Visitor<'de> for BooleanVisitor {
+    type Value = Boolean;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a Z40/boolean")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Boolean, E> {
+        Boolean::from_zid(v)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Boolean, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        // the key that the value was read from, and the value.
+        let mut value: Option<(&str, Boolean)> = None;
+        while let Some(key) = map.next_key::<Key<'de>>()? {
+            // `Z9K1` is the key of a boolean written as a reference.
+            let (key, zid) = match &*key {
+                "Z40K1" => ("Z40K1", map.next_value::<Object<Reference>>()?.value.id),
+                "Z9K1" => ("Z9K1", map.next_value::<String>()?),
+                other => return Err(A::Error::unknown_field(other, &["Z40K1", "Z9K1"])),
+            };
+            match value {
+                Some((seen, _)) if seen == key => return Err(A::Error::duplicate_field(key)),
+                Some((seen, _)) => {
+                    return Err(A::Error::custom(format_args!(
+                        "a boolean has either {seen} or {key}, not both"
+                    )))
+                }
+                None => value = Some((key, Boolean::from_zid(&zid)?)),
+            }
+        }
+        value
+            .map(|(_, value)| value)
+            .ok_or_else(|| A::Error::missing_field("Z40K1"))
+    }
+}
+
+impl<'de> Deserialize<'de> for Boolean {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BooleanVisitor)
+    }
+}
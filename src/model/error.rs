@@ -0,0 +1,362 @@
+//! Z5/errors and the standard Z50/error types.
+//!
+//! The value of an error (`Z5K2`) is an object whose type is a call to Z885, the
+//! function that builds the type of an error from its error type. Its keys are
+//! the keys of the error type, such as `Z507K1` and `Z507K2`:
+//!
+//! ```json
+//! {
+//!     "Z1K1": "Z5",
+//!     "Z5K1": "Z500",
+//!     "Z5K2": {
+//!         "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z885", "Z885K1": "Z500" },
+//!         "Z500K1": "something went wrong"
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::de::{Error as _, IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::object::de::Key;
+use super::value::ZMap;
+use super::{Object, Reference, ZObject, ZValue};
+use crate::label;
+
+macro_rules! error_types {
+    ($($(#[$attr:meta])* $name:ident = $id:literal, $description:literal;)*) => {
+        /// The Z50/error types: the standard ones, and from Z580 on those that
+        /// the evaluator reports.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ErrorType {
+            $($(#[$attr])* $name,)*
+        }
+
+        impl ErrorType {
+            /// The number of the ZID of this error type, such as `500` for `Z500`.
+            pub fn id(self) -> u32 {
+                match self {
+                    $(ErrorType::$name => $id,)*
+                }
+            }
+
+            pub fn from_id(id: u32) -> Option<ErrorType> {
+                match id {
+                    $($id => Some(ErrorType::$name),)*
+                    _ => None,
+                }
+            }
+
+            /// A short English description of this error type.
+            pub fn description(self) -> &'static str {
+                match self {
+                    $(ErrorType::$name => $description,)*
+                }
+            }
+        }
+    };
+}
+
+error_types! {
+    /// `Z500K1`: the error information.
+    Generic = 500, "generic error";
+    /// `Z501K1`: the message, `Z501K2`: the input.
+    JsonSyntax = 501, "JSON syntax error";
+    /// `Z502K1`: the subtype, `Z502K2`: the value.
+    NotWellFormed = 502, "not well-formed";
+    /// `Z503K1`: the feature name.
+    NotImplemented = 503, "not implemented yet";
+    /// `Z504K1`: the ZID.
+    ZidNotFound = 504, "ZID not found";
+    /// `Z505K1`: the expected number, `Z505K2`: the actual number, `Z505K3`: the arguments.
+    ArgumentCountMismatch = 505, "number of arguments mismatch";
+    /// `Z506K1`: the expected type, `Z506K2`: the actual type, `Z506K3`: the argument,
+    /// `Z506K4`: the propagated error.
+    ArgumentTypeMismatch = 506, "argument type mismatch";
+    /// `Z507K1`: the function call, `Z507K2`: the propagated error.
+    ErrorInEvaluation = 507, "error in evaluation";
+    /// `Z508K1`: the keys.
+    CompetingKeys = 508, "competing keys";
+    /// `Z509K1`: the errors.
+    ListOfErrors = 509, "list of errors";
+    /// `Z511K1`: the key, `Z511K2`: the object.
+    KeyNotFound = 511, "key not found";
+    /// `Z512K1`: the expected result, `Z512K2`: the actual result.
+    TestFailed = 512, "test failed";
+    /// `Z516K1`: the argument, `Z516K2`: the bad value.
+    ArgumentValueError = 516, "argument value error";
+    /// `Z517K1`: the expected type, `Z517K2`: the actual type, `Z517K3`: the returned
+    /// value, `Z517K4`: the propagated error.
+    ReturnTypeMismatch = 517, "return type mismatch";
+    /// `Z518K1`: the expected type, `Z518K2`: the object.
+    ObjectTypeMismatch = 518, "object type mismatch";
+    /// `Z580K1`: the message, `Z580K2`: the line, `Z580K3`: the column. The line and
+    /// column are Z13518/natural numbers counted from 1 in the code of the
+    /// implementation, and are left out if the error is not in it.
+    CompileError = 580, "compile error";
+    /// `Z581K1`: the wall-clock time limit in milliseconds, as a Z13518/natural number.
+    TimeLimit = 581, "time limit exceeded";
+    /// `Z582K1`: the CPU time limit in seconds, as a Z13518/natural number.
+    CpuTimeLimit = 582, "CPU time limit exceeded";
+    /// `Z583K1`: the limit on the address space in bytes, as a Z13518/natural number.
+    MemoryLimit = 583, "memory limit exceeded";
+    /// The implementation made a system call that the sandbox does not allow.
+    SandboxViolation = 584, "sandbox violation";
+    /// `Z585K1`: the panic message, `Z585K2`: the line, `Z585K3`: the column,
+    /// `Z585K4`: the backtrace. The line and column are Z13518/natural numbers like
+    /// those of a Z580/compile error. The backtrace is left out if none was captured.
+    Panic = 585, "panic";
+}
+
+impl ErrorType {
+    pub fn zid(self) -> String {
+        format!("Z{}", self.id())
+    }
+}
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Z{}/{}", self.id(), self.description())
+    }
+}
+
+/// A Z5/error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZError {
+    /// The ZID of the Z50/error type, such as `Z507`.
+    pub error_type: String,
+    /// The keys of the value of the error, such as `Z507K1`, without its `Z1K1`.
+    pub args: ZMap,
+}
+
+impl ZError {
+    pub fn new(ty: ErrorType) -> ZError {
+        ZError {
+            error_type: ty.zid(),
+            args: ZMap::new(),
+        }
+    }
+
+    /// The error type, if it is one of the standard ones.
+    pub fn ty(&self) -> Option<ErrorType> {
+        let id = self.error_type.strip_prefix('Z')?.parse().ok()?;
+        ErrorType::from_id(id)
+    }
+
+    /// Sets the `n`th key of the error, `Z500K1` for `1` if this is a Z500.
+    pub fn with_arg(mut self, n: u32, value: impl Into<ZValue>) -> ZError {
+        self.args
+            .insert(format!("{}K{n}", self.error_type), value.into());
+        self
+    }
+
+    /// Gets the `n`th key of the error.
+    pub fn arg(&self, n: u32) -> Option<&ZValue> {
+        self.args.get(&format!("{}K{n}", self.error_type))
+    }
+
+    /// The type of the value of this error: a call to Z885 with the error type.
+    pub fn value_type(&self) -> ZValue {
+        [
+            ("Z1K1".to_owned(), ZValue::Reference("Z7".to_owned())),
+            ("Z7K1".to_owned(), ZValue::Reference("Z885".to_owned())),
+            (
+                "Z885K1".to_owned(),
+                ZValue::Reference(self.error_type.clone()),
+            ),
+        ]
+        .into_iter()
+        .collect::<ZMap>()
+        .into()
+    }
+
+    /// A Z500/generic error with a message.
+    pub fn generic(message: impl Into<String>) -> ZError {
+        ZError::new(ErrorType::Generic).with_arg(1, message.into())
+    }
+
+    /// A Z503/not implemented yet error for a feature.
+    pub fn not_implemented(feature: impl Into<String>) -> ZError {
+        ZError::new(ErrorType::NotImplemented).with_arg(1, feature.into())
+    }
+
+    /// A Z504/ZID not found error.
+    pub fn zid_not_found(zid: impl Into<String>) -> ZError {
+        ZError::new(ErrorType::ZidNotFound).with_arg(1, zid.into())
+    }
+
+    /// A Z505/number of arguments mismatch error.
+    pub fn argument_count_mismatch(expected: usize, actual: usize) -> ZError {
+        ZError::new(ErrorType::ArgumentCountMismatch)
+            .with_arg(1, expected.to_string())
+            .with_arg(2, actual.to_string())
+    }
+
+    /// A Z506/argument type mismatch error for the argument with the key `argument`.
+    pub fn argument_type_mismatch(
+        expected: impl Into<ZValue>,
+        actual: impl Into<ZValue>,
+        argument: impl Into<String>,
+    ) -> ZError {
+        ZError::new(ErrorType::ArgumentTypeMismatch)
+            .with_arg(1, expected)
+            .with_arg(2, actual)
+            .with_arg(3, argument.into())
+    }
+
+    /// A Z507/error in evaluation, wrapping the error that `function_call` ran into.
+    pub fn in_evaluation(function_call: impl Into<ZValue>, error: ZError) -> ZError {
+        ZError::new(ErrorType::ErrorInEvaluation)
+            .with_arg(1, function_call)
+            .with_arg(2, error)
+    }
+
+    /// A Z509/list of errors.
+    pub fn list(errors: impl IntoIterator<Item = ZError>) -> ZError {
+        let errors = errors.into_iter().map(ZValue::from);
+        ZError::new(ErrorType::ListOfErrors)
+            .with_arg(1, ZValue::list(ZValue::Reference("Z5".to_owned()), errors))
+    }
+
+    /// A Z511/key not found error.
+    pub fn key_not_found(key: impl Into<String>, object: impl Into<ZValue>) -> ZError {
+        ZError::new(ErrorType::KeyNotFound)
+            .with_arg(1, key.into())
+            .with_arg(2, object)
+    }
+
+    /// A Z517/return type mismatch error.
+    pub fn return_type_mismatch(expected: impl Into<ZValue>, actual: impl Into<ZValue>) -> ZError {
+        ZError::new(ErrorType::ReturnTypeMismatch)
+            .with_arg(1, expected)
+            .with_arg(2, actual)
+    }
+}
+
+impl fmt::Display for ZError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty() {
+            Some(ty) => write!(f, "{ty}")?,
+            None => f.write_str(&self.error_type)?,
+        }
+        for (_, value) in self.args.iter() {
+            if let Some(s) = value.as_str() {
+                write!(f, ": {s}")?;
+            } else if let Ok(error) = value.clone().into_object::<ZError>() {
+                write!(f, ": {}", error.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ZError {}
+
+impl From<ZError> for ZValue {
+    fn from(error: ZError) -> ZValue {
+        let mut value = ZMap::new();
+        value.insert("Z1K1".to_owned(), error.value_type());
+        value.extend(error.args);
+        [
+            ("Z1K1".to_owned(), ZValue::Reference("Z5".to_owned())),
+            ("Z5K1".to_owned(), ZValue::Reference(error.error_type)),
+            ("Z5K2".to_owned(), value.into()),
+        ]
+        .into_iter()
+        .collect::<ZMap>()
+        .into()
+    }
+}
+
+impl ZObject for ZError {
+    type ZType = Object<Reference<label::Z5>>;
+}
+
+/// The value of an error, with its type written first.
+struct ErrorValue<'a>(&'a ZError);
+
+impl Serialize for ErrorValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.args.len() + 1))?;
+        map.serialize_entry("Z1K1", &self.0.value_type())?;
+        for (key, value) in self.0.args.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for ZError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry(
+            "Z5K1",
+            &Object::new(Reference {
+                id: &self.error_type,
+            }),
+        )?;
+        map.serialize_entry("Z5K2", &ErrorValue(self))?;
+        map.end()
+    }
+}
+
+struct ZErrorVisitor;
+
+impl<'de> Visitor<'de> for ZErrorVisitor {
+    type Value = ZError;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a Z5/error")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<ZError, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut error_type = None;
+        let mut args = None;
+        while let Some(key) = map.next_key::<Key<'de>>()? {
+            match &*key {
+                "Z5K1" if error_type.is_some() => return Err(A::Error::duplicate_field("Z5K1")),
+                "Z5K1" => error_type = Some(map.next_value::<Object<Reference>>()?.value.id),
+                "Z5K2" if args.is_some() => return Err(A::Error::duplicate_field("Z5K2")),
+                "Z5K2" => {
+                    let ZValue::Object(mut value) = map.next_value()? else {
+                        return Err(A::Error::custom(
+                            "expected the value of an error to be an object",
+                        ));
+                    };
+                    value.remove("Z1K1");
+                    args = Some(value);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let error_type = error_type.ok_or_else(|| A::Error::missing_field("Z5K1"))?;
+        Ok(ZError {
+            error_type,
+            args: args.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for ZError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(ZErrorVisitor)
+    }
+}
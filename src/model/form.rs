@@ -0,0 +1,100 @@
+//! Selecting between the normal and canonical forms of ZObjects.
+//!
+//! In normal form, every string is written as `{"Z1K1": "Z6", "Z6K1": ...}`,
+//! every reference as `{"Z1K1": "Z9", "Z9K1": ...}` and every list as nested
+//! `K1`/`K2` maps. Canonical form writes strings and references bare and lists
+//! as JSON arrays headed by their element type.
+//!
+//! Deserialization accepts both forms. Serialization writes normal form unless
+//! the value is wrapped in [`Canonical`].
+
+use std::cell::Cell;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::ZValue;
+
+/// The form ZObjects are serialized in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Form {
+    #[default]
+    Normal,
+    Canonical,
+}
+
+thread_local! {
+    static FORM: Cell<Form> = const { Cell::new(Form::Normal) };
+}
+
+/// The form that is currently being serialized.
+pub fn current() -> Form {
+    FORM.with(Cell::get)
+}
+
+/// Runs `f` with objects being serialized in `form`.
+pub fn with_form<R>(form: Form, f: impl FnOnce() -> R) -> R {
+    struct Restore(Form);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORM.with(|c| c.set(self.0));
+        }
+    }
+
+    let _restore = Restore(FORM.with(|c| c.replace(form)));
+    f()
+}
+
+/// Serializes the inner value in canonical form.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Canonical<T>(pub T);
+
+/// Serializes the inner value in normal form.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Normal<T>(pub T);
+
+impl<T: Serialize> Serialize for Canonical<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        with_form(Form::Canonical, || self.0.serialize(serializer))
+    }
+}
+
+impl<T: Serialize> Serialize for Normal<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        with_form(Form::Normal, || self.0.serialize(serializer))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Canonical<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Canonical)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Normal<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Normal)
+    }
+}
+
+/// Reads a ZObject in either form and writes it out in `form`.
+pub fn convert<'de, D, S>(form: Form, deserializer: D, serializer: S) -> Result<S::Ok, S::Error>
+where
+    D: Deserializer<'de>,
+    S: Serializer,
+{
+    let value = ZValue::deserialize(deserializer).map_err(serde::ser::Error::custom)?;
+    with_form(form, || value.serialize(serializer))
+}
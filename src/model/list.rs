@@ -1,12 +1,14 @@
 use std::marker::PhantomData;
 use std::slice;
 
-use serde::de::{DeserializeSeed, Error, Visitor};
-use serde::ser::SerializeMap;
+use serde::de::{DeserializeSeed, Error, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
 use serde::{Deserialize, Serialize};
 
 use crate::label;
 
+use super::form::{self, Form};
+use super::object::ZType;
 use super::{FunctionCall, Object, Reference, TypeListArgs, ZObject};
 
 /// A Z881/Typed list
@@ -15,19 +17,41 @@ pub struct TypedList<T: ZObject> {
     pub inner: Vec<T>,
 }
 
-// (de)serialization for typed lists. typed lists are represented as a linked list T
-// with elements E where T is either (E, T1) where T1 is another typed list, or ().
+/// Typed lists in normal form nest once per element, so (de)serializing them
+/// recurses once per element. Before going one level deeper, make sure that there
+/// is enough stack left, and continue on a new stack segment if there is not.
+pub(super) fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    const RED_ZONE: usize = 64 * 1024;
+    const STACK_SIZE: usize = 2 * 1024 * 1024;
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, f)
+}
+
+// (de)serialization for typed lists. In normal form, typed lists are represented as a
+// linked list T with elements E where T is either (E, T1) where T1 is another typed list,
+// or (). In canonical form, they are an array of the element type followed by the elements.
+
+/// The keys of a typed list in normal form.
+#[derive(Deserialize)]
+#[serde(field_identifier)]
+enum ListKey {
+    Z1K1,
+    K1,
+    K2,
+}
 
+/// Deserializes a typed list in normal form. Keys may appear in any order, so the
+/// elements are collected in reverse: the tail of the list is complete before its
+/// head is pushed.
 pub struct TypedListVisitor<T> {
-    entries: Vec<T>,
     expecting_type: bool,
+    ph: PhantomData<T>,
 }
 
 impl<'de, T> DeserializeSeed<'de> for TypedListVisitor<T>
 where
     T: ZObject + Deserialize<'de>,
 {
-    type Value = TypedListVisitor<T>;
+    type Value = Vec<T>;
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
@@ -40,46 +64,88 @@ impl<'de, T> Visitor<'de> for TypedListVisitor<T>
 where
     T: ZObject + Deserialize<'de>,
 {
-    type Value = TypedListVisitor<T>;
+    type Value = Vec<T>;
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         formatter.pad("a map")
     }
-    fn visit_map<A>(mut self, mut map: A) -> Result<TypedListVisitor<T>, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<Vec<T>, A::Error>
     where
         A: serde::de::MapAccess<'de>,
     {
-        if self.expecting_type {
-            let Some((k, _)) = map.next_entry::<String, <TypedList<T> as ZObject>::ZType>()? else {
-                return Err(A::Error::custom("expected Z1K1 entry"));
-            };
-
-            if k != "Z1K1" {
-                return Err(A::Error::custom("expected Z1K1"));
+        let mut has_type = false;
+        let mut head = None;
+        let mut tail = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ListKey::Z1K1 if has_type => return Err(A::Error::duplicate_field("Z1K1")),
+                ListKey::Z1K1 => {
+                    map.next_value::<<TypedList<T> as ZObject>::ZType>()?;
+                    has_type = true;
+                }
+                ListKey::K1 if head.is_some() => return Err(A::Error::duplicate_field("K1")),
+                ListKey::K1 => head = Some(map.next_value::<Object<T>>()?.value),
+                ListKey::K2 if tail.is_some() => return Err(A::Error::duplicate_field("K2")),
+                ListKey::K2 => {
+                    tail = Some(grow_stack(|| {
+                        map.next_value_seed(TypedListVisitor {
+                            expecting_type: true,
+                            ph: PhantomData,
+                        })
+                    })?)
+                }
             }
-            self.expecting_type = false;
         }
-        if let Some((k, v)) = map.next_entry::<String, Object<T>>()? {
-            if k != "K1" {
-                return Err(A::Error::custom("expected K1"));
-            }
-            self.entries.push(v.value);
-
-            self.expecting_type = true;
-            let Some((k, mut this)) =
-                map.next_entry_seed::<PhantomData<String>, Self>(PhantomData, self)?
-            else {
-                return Err(A::Error::custom("expected K2 entry after K1"));
-            };
-            this.expecting_type = false;
-
-            if k != "K2" {
-                return Err(A::Error::custom("expected K2 after K1"));
+
+        if self.expecting_type && !has_type {
+            return Err(A::Error::missing_field("Z1K1"));
+        }
+
+        match (head, tail) {
+            (Some(head), Some(mut entries)) => {
+                entries.push(head);
+                Ok(entries)
             }
+            (None, None) => Ok(Vec::new()),
+            (Some(_), None) => Err(A::Error::missing_field("K2")),
+            (None, Some(_)) => Err(A::Error::missing_field("K1")),
+        }
+    }
+}
 
-            Ok(this)
-        } else {
-            Ok(self)
+struct ListVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ListVisitor<T>
+where
+    T: ZObject + Deserialize<'de>,
+{
+    type Value = TypedList<T>;
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.pad("a typed list")
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<TypedList<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        if seq.next_element::<<T::ZType as ZType>::Value>()?.is_none() {
+            return Err(A::Error::custom("expected the element type of the list"));
+        }
+        let mut inner = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element::<Object<T>>()? {
+            inner.push(v.value);
         }
+        Ok(TypedList { inner })
+    }
+    fn visit_map<A>(self, map: A) -> Result<TypedList<T>, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut inner = TypedListVisitor {
+            expecting_type: false,
+            ph: PhantomData,
+        }
+        .visit_map(map)?;
+        inner.reverse();
+        Ok(TypedList { inner })
     }
 }
 
@@ -88,13 +154,7 @@ impl<'de, T: ZObject + Deserialize<'de>> Deserialize<'de> for TypedList<T> {
     where
         D: serde::Deserializer<'de>,
     {
-        let visitor = deserializer.deserialize_map(TypedListVisitor {
-            entries: Vec::new(),
-            expecting_type: false,
-        })?;
-        Ok(TypedList {
-            inner: visitor.entries,
-        })
+        deserializer.deserialize_any(ListVisitor(PhantomData))
     }
 }
 
@@ -102,7 +162,7 @@ struct TypedListSerializeImpl<'a, T> {
     iter: slice::Iter<'a, T>,
 }
 
-impl<'a, T: ZObject> ZObject for TypedListSerializeImpl<'a, T> {
+impl<T: ZObject> ZObject for TypedListSerializeImpl<'_, T> {
     type ZType = <TypedList<T> as ZObject>::ZType;
 }
 
@@ -117,7 +177,9 @@ impl<T: ZObject + Serialize> Serialize for TypedListSerializeImpl<'_, T> {
         if let Some(val) = iter.next() {
             let mut serializer = serializer.serialize_map(Some(2))?;
             serializer.serialize_entry("K1", &Object::new(val))?;
-            serializer.serialize_entry("K2", &Object::new(TypedListSerializeImpl { iter }))?;
+            grow_stack(|| {
+                serializer.serialize_entry("K2", &Object::new(TypedListSerializeImpl { iter }))
+            })?;
             serializer.end()
         } else {
             serializer.serialize_map(Some(0))?.end()
@@ -130,6 +192,14 @@ impl<T: ZObject + Serialize> Serialize for TypedList<T> {
     where
         S: serde::Serializer,
     {
+        if form::current() == Form::Canonical {
+            let mut seq = serializer.serialize_seq(Some(self.inner.len() + 1))?;
+            seq.serialize_element(&<T::ZType as ZType>::Value::default())?;
+            for val in &self.inner {
+                seq.serialize_element(&Object::new(val))?;
+            }
+            return seq.end();
+        }
         TypedListSerializeImpl {
             iter: self.inner.iter(),
         }
@@ -139,5 +209,6 @@ impl<T: ZObject + Serialize> Serialize for TypedList<T> {
 
 impl<T: ZObject> ZObject for TypedList<T> {
     type ZType =
-        Object<FunctionCall<Reference<label::Z881>, TypeListArgs<Object<Reference<T::ZType>>>>>;
+        Object<FunctionCall<Reference<label::Z881>, TypeListArgs<<T::ZType as ZType>::Value>>>;
+    const CANONICAL_BARE: bool = true;
 }
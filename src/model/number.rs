@@ -0,0 +1,288 @@
+//! Numbers: Z13518/natural numbers, Z16683/integers and Z20838/float64s, and their
+//! conversions to and from Rust numbers.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::error::ErrorType;
+use super::{Object, Reference, ZError, ZObject, ZString, ZValue};
+use crate::label;
+
+/// Defines an enumeration whose values are written as references to its instances.
+macro_rules! instances {
+    ($(#[$attr:meta])* $name:ident, $expecting:literal { $($(#[$vattr:meta])* $variant:ident = $zid:literal,)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($(#[$vattr])* $variant,)*
+        }
+
+        impl $name {
+            /// The ZID of the instance.
+            pub fn zid(self) -> &'static str {
+                match self {
+                    $($name::$variant => $zid,)*
+                }
+            }
+
+            pub fn from_zid(zid: &str) -> Option<$name> {
+                match zid {
+                    $($zid => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl ZObject for $name {
+            type ZType = label::Z9;
+            const CANONICAL_BARE: bool = true;
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                Reference { id: self.zid() }.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let Reference { id } = Reference::<String>::deserialize(deserializer)?;
+                $name::from_zid(&id).ok_or_else(|| {
+                    D::Error::invalid_value(serde::de::Unexpected::Str(&id), &$expecting)
+                })
+            }
+        }
+    };
+}
+
+instances! {
+    /// A Z16659/sign.
+    Sign, "a Z16659/sign" {
+        Positive = "Z16660",
+        /// The sign of zero.
+        Neutral = "Z16661",
+        Negative = "Z16662",
+    }
+}
+
+instances! {
+    /// A Z20825/float64 special value, which says whether a float is infinite or
+    /// not a number.
+    SpecialValue, "a Z20825/float64 special value" {
+        Neither = "Z20826",
+        PositiveInfinity = "Z20827",
+        NegativeInfinity = "Z20828",
+        NotANumber = "Z20829",
+    }
+}
+
+/// A Z13518/natural number, written as its decimal digits.
+#[derive(ZObject, Debug)]
+#[zobject(Z13518)]
+pub struct NaturalNumber {
+    #[zobject(K1)]
+    pub value: ZString,
+}
+
+impl NaturalNumber {
+    /// The decimal digits of the number, if it is well-formed.
+    pub fn digits(&self) -> Option<&str> {
+        let digits = &self.value.value;
+        let valid = !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+        valid.then_some(digits.as_str())
+    }
+}
+
+impl From<u64> for NaturalNumber {
+    fn from(n: u64) -> Self {
+        NaturalNumber {
+            value: ZString {
+                value: n.to_string(),
+            },
+        }
+    }
+}
+
+impl From<&str> for NaturalNumber {
+    fn from(digits: &str) -> Self {
+        NaturalNumber {
+            value: digits.into(),
+        }
+    }
+}
+
+impl From<NaturalNumber> for ZValue {
+    fn from(n: NaturalNumber) -> ZValue {
+        ZValue::from_object(&Object::new(n)).expect("natural numbers are always valid objects")
+    }
+}
+
+impl TryFrom<&NaturalNumber> for u64 {
+    type Error = ZError;
+    fn try_from(n: &NaturalNumber) -> Result<Self, Self::Error> {
+        let digits = n
+            .digits()
+            .ok_or_else(|| not_well_formed("Z13518/natural number", n.value.value.as_str()))?;
+        digits
+            .parse()
+            .map_err(|_| out_of_range("u64", NaturalNumber::from(digits)))
+    }
+}
+
+/// A Z16683/integer, written as its sign and its absolute value.
+#[derive(ZObject, Debug)]
+#[zobject(Z16683)]
+pub struct Integer {
+    #[zobject(K1)]
+    pub sign: Sign,
+    #[zobject(K2)]
+    pub absolute_value: NaturalNumber,
+}
+
+impl From<i64> for Integer {
+    fn from(n: i64) -> Self {
+        let sign = match n {
+            ..0 => Sign::Negative,
+            0 => Sign::Neutral,
+            1.. => Sign::Positive,
+        };
+        Integer {
+            sign,
+            absolute_value: n.unsigned_abs().into(),
+        }
+    }
+}
+
+impl From<Integer> for ZValue {
+    fn from(n: Integer) -> ZValue {
+        ZValue::from_object(&Object::new(n)).expect("integers are always valid objects")
+    }
+}
+
+impl TryFrom<&Integer> for i64 {
+    type Error = ZError;
+    fn try_from(n: &Integer) -> Result<Self, Self::Error> {
+        let digits = n.absolute_value.digits().ok_or_else(|| {
+            not_well_formed(
+                "Z13518/natural number",
+                n.absolute_value.value.value.as_str(),
+            )
+        })?;
+        let copy = || Integer {
+            sign: n.sign,
+            absolute_value: digits.into(),
+        };
+        // zero, and only zero, has the neutral sign.
+        if digits.bytes().all(|b| b == b'0') != (n.sign == Sign::Neutral) {
+            return Err(not_well_formed("Z16683/integer", copy()));
+        }
+        let abs = digits.parse::<u64>().ok();
+        let value = match n.sign {
+            Sign::Negative => abs.and_then(|abs| 0i64.checked_sub_unsigned(abs)),
+            Sign::Neutral | Sign::Positive => abs.and_then(|abs| i64::try_from(abs).ok()),
+        };
+        value.ok_or_else(|| out_of_range("i64", copy()))
+    }
+}
+
+/// A Z20838/float64. The exponent and the mantissa are the fields of the IEEE 754
+/// binary64 number, with the exponent unbiased, so that a normal number is
+/// `(1 + mantissa / 2^52) * 2^exponent` and zeros and subnormal numbers have an
+/// exponent of -1023. Negative zero has a negative sign.
+#[derive(ZObject, Debug)]
+#[zobject(Z20838)]
+pub struct Float64 {
+    #[zobject(K1)]
+    pub sign: Sign,
+    #[zobject(K2)]
+    pub exponent: Integer,
+    #[zobject(K3)]
+    pub mantissa: NaturalNumber,
+    #[zobject(K4)]
+    pub special: SpecialValue,
+}
+
+const MANTISSA_BITS: u32 = 52;
+const EXPONENT_BIAS: i64 = 1023;
+
+impl From<f64> for Float64 {
+    fn from(f: f64) -> Self {
+        let bits = f.to_bits();
+        let sign = if f.is_sign_negative() {
+            Sign::Negative
+        } else if f == 0.0 {
+            Sign::Neutral
+        } else {
+            Sign::Positive
+        };
+        let special = if f.is_nan() {
+            SpecialValue::NotANumber
+        } else if f == f64::INFINITY {
+            SpecialValue::PositiveInfinity
+        } else if f == f64::NEG_INFINITY {
+            SpecialValue::NegativeInfinity
+        } else {
+            SpecialValue::Neither
+        };
+        let biased = ((bits >> MANTISSA_BITS) & 0x7ff) as i64;
+        Float64 {
+            sign,
+            exponent: (biased - EXPONENT_BIAS).into(),
+            mantissa: (bits & ((1 << MANTISSA_BITS) - 1)).into(),
+            special,
+        }
+    }
+}
+
+impl TryFrom<&Float64> for f64 {
+    type Error = ZError;
+    fn try_from(f: &Float64) -> Result<Self, Self::Error> {
+        let negative = f.sign == Sign::Negative;
+        match f.special {
+            SpecialValue::PositiveInfinity => return Ok(f64::INFINITY),
+            SpecialValue::NegativeInfinity => return Ok(f64::NEG_INFINITY),
+            SpecialValue::NotANumber if negative => return Ok(-f64::NAN),
+            SpecialValue::NotANumber => return Ok(f64::NAN),
+            SpecialValue::Neither => {}
+        }
+        let exponent = i64::try_from(&f.exponent)?;
+        let mantissa = u64::try_from(&f.mantissa)?;
+        // the largest exponent is that of infinities and NaNs, which are special values.
+        if !(-EXPONENT_BIAS..EXPONENT_BIAS + 1).contains(&exponent) {
+            return Err(out_of_range(
+                "the exponent of a float64",
+                Integer::from(exponent),
+            ));
+        }
+        if mantissa >> MANTISSA_BITS != 0 {
+            return Err(out_of_range(
+                "the mantissa of a float64",
+                NaturalNumber::from(mantissa),
+            ));
+        }
+        let biased = (exponent + EXPONENT_BIAS) as u64;
+        let sign = u64::from(negative) << 63;
+        Ok(f64::from_bits(sign | biased << MANTISSA_BITS | mantissa))
+    }
+}
+
+/// A Z502/not well-formed error for a `value` that is not a valid `ty`.
+fn not_well_formed(ty: &str, value: impl Into<ZValue>) -> ZError {
+    ZError::new(ErrorType::NotWellFormed)
+        .with_arg(1, ty)
+        .with_arg(2, value)
+}
+
+/// A Z516/argument value error for a number `value` that does not fit in `ty`,
+/// as the evaluator reports for arguments that do not fit in their parameter.
+fn out_of_range(ty: &str, value: impl Into<ZValue>) -> ZError {
+    ZError::new(ErrorType::ArgumentValueError)
+        .with_arg(1, ty)
+        .with_arg(2, value)
+}
@@ -1,8 +1,17 @@
 use std::fmt;
+use std::marker::PhantomData;
 
-use serde::de::DeserializeOwned;
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{DeserializeOwned, Error, IntoDeserializer, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use self::de::ObjectMapAccess;
+use self::ser::ObjectSerializer;
+use super::form::{self, Form};
+
+pub(crate) mod de;
+mod ser;
+
 pub fn serialize<T: ZObject + Serialize, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -25,11 +34,9 @@ where
 /// Represents a Z1/object. This should not be used for fields.
 /// Instead, use the [`serialize`] and [`deserialize`] functions,
 /// with the `#[serde(with = "wikifunctions::object")]` attribute.
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Default)]
 pub struct Object<T: ZObject> {
-    #[serde(rename = "Z1K1")]
     pub ty: T::ZType,
-    #[serde(flatten)] // TODO avoid flatten
     pub value: T,
 }
 
@@ -42,10 +49,105 @@ impl<T: ZObject> Object<T> {
     }
 }
 
+impl<T: ZObject + Serialize> Serialize for Object<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if T::SELF_TYPED || (T::CANONICAL_BARE && form::current() == Form::Canonical) {
+            return self.value.serialize(serializer);
+        }
+        self.value.serialize(ObjectSerializer {
+            inner: serializer,
+            ty: &self.ty,
+        })
+    }
+}
+
+struct ObjectVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ZObject + Deserialize<'de>> Visitor<'de> for ObjectVisitor<T> {
+    type Value = Object<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a ZObject")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Object<T>, E> {
+        T::deserialize(v.into_deserializer()).map(Object::new)
+    }
+
+    fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Object<T>, E> {
+        T::deserialize(BorrowedStrDeserializer::new(v)).map(Object::new)
+    }
+
+    fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Object<T>, E> {
+        T::deserialize(v.into_deserializer()).map(Object::new)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Object<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        T::deserialize(SeqAccessDeserializer::new(seq)).map(Object::new)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Object<T>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut ty = None;
+        let value = T::deserialize(MapAccessDeserializer::new(ObjectMapAccess {
+            map,
+            ty: &mut ty,
+        }))?;
+        let ty = ty.ok_or_else(|| A::Error::missing_field("Z1K1"))?;
+        Ok(Object { ty, value })
+    }
+}
+
+impl<'de, T: ZObject + Deserialize<'de>> Deserialize<'de> for Object<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if T::SELF_TYPED {
+            return T::deserialize(deserializer).map(Object::new);
+        }
+        deserializer.deserialize_any(ObjectVisitor(PhantomData))
+    }
+}
+
 pub trait ZObject {
-    type ZType: DeserializeOwned + Serialize + fmt::Debug + Default;
+    type ZType: ZType;
+
+    /// Whether objects of this type are written without a `Z1K1` in canonical
+    /// form, like strings, references and lists.
+    const CANONICAL_BARE: bool = false;
+    /// Whether values of this type write their own `Z1K1`, like [`ZValue`]. The
+    /// `ty` of an `Object` of such a type is not used.
+    ///
+    /// [`ZValue`]: super::ZValue
+    const SELF_TYPED: bool = false;
 }
 
 impl<T: ZObject> ZObject for &'_ T {
     type ZType = T::ZType;
+    const CANONICAL_BARE: bool = T::CANONICAL_BARE;
+    const SELF_TYPED: bool = T::SELF_TYPED;
+}
+
+/// A type that can appear as the `Z1K1` of an object.
+pub trait ZType: DeserializeOwned + Serialize + fmt::Debug + Default {
+    /// This type when used as a value rather than as a `Z1K1`. The `Z1K1`
+    /// of strings and references is a bare label, which becomes a Z9/reference
+    /// everywhere else.
+    type Value: DeserializeOwned + Serialize + fmt::Debug + Default;
+}
+
+impl<T> ZType for Object<T>
+where
+    T: ZObject + DeserializeOwned + Serialize + fmt::Debug + Default,
+{
+    type Value = Self;
 }
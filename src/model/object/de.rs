@@ -0,0 +1,98 @@
+//! Reading the value of an object from the same map as its `Z1K1`.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{DeserializeSeed, Error, IntoDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+/// A key of an object, borrowed from the input if possible.
+pub struct Key<'de>(pub Cow<'de, str>);
+
+impl Deref for Key<'_> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = Key<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.pad("a key")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Key<'de>, E> {
+                Ok(Key(Cow::Owned(v.to_owned())))
+            }
+
+            fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Key<'de>, E> {
+                Ok(Key(Cow::Borrowed(v)))
+            }
+
+            fn visit_string<E: Error>(self, v: String) -> Result<Key<'de>, E> {
+                Ok(Key(Cow::Owned(v)))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+/// Passes the entries of an object to the deserializer of its value, taking out the
+/// `Z1K1` wherever it appears. The entries are not buffered, so keys and values can
+/// borrow from the input.
+pub(super) struct ObjectMapAccess<'a, A, Ty> {
+    pub(super) map: A,
+    pub(super) ty: &'a mut Option<Ty>,
+}
+
+impl<'de, A, Ty> MapAccess<'de> for ObjectMapAccess<'_, A, Ty>
+where
+    A: MapAccess<'de>,
+    Ty: Deserialize<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        loop {
+            match self.map.next_key::<Key<'de>>()? {
+                None => return Ok(None),
+                Some(key) if &*key == "Z1K1" => {
+                    if self.ty.is_some() {
+                        return Err(A::Error::duplicate_field("Z1K1"));
+                    }
+                    *self.ty = Some(self.map.next_value()?);
+                }
+                Some(Key(Cow::Borrowed(key))) => {
+                    return seed
+                        .deserialize(BorrowedStrDeserializer::new(key))
+                        .map(Some)
+                }
+                Some(Key(Cow::Owned(key))) => {
+                    return seed.deserialize(key.into_deserializer()).map(Some)
+                }
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
@@ -0,0 +1,205 @@
+//! Writing the value of an object into the same map as its `Z1K1`.
+
+use serde::ser::{Error, Impossible, SerializeMap, SerializeStruct};
+use serde::{Serialize, Serializer};
+
+/// Serializes the value of an object, which must be a struct or a map, as a map
+/// that starts with the `Z1K1` of the object. The entries of the value are written
+/// directly to the underlying serializer.
+pub(super) struct ObjectSerializer<'a, S, Ty> {
+    pub(super) inner: S,
+    pub(super) ty: &'a Ty,
+}
+
+impl<S: Serializer, Ty: Serialize> ObjectSerializer<'_, S, Ty> {
+    fn begin(self, len: Option<usize>) -> Result<S::SerializeMap, S::Error> {
+        let mut map = self.inner.serialize_map(len.map(|len| len + 1))?;
+        map.serialize_entry("Z1K1", self.ty)?;
+        Ok(map)
+    }
+}
+
+fn not_an_object<T, E: Error>(found: &str) -> Result<T, E> {
+    Err(E::custom(format_args!(
+        "expected the value of an object to be a struct or a map, found {found}"
+    )))
+}
+
+impl<S: Serializer, Ty: Serialize> Serializer for ObjectSerializer<'_, S, Ty> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+    type SerializeMap = S::SerializeMap;
+    type SerializeStruct = ObjectStructSerializer<S::SerializeMap>;
+    type SerializeStructVariant = Impossible<S::Ok, S::Error>;
+
+    fn serialize_map(self, len: Option<usize>) -> Result<S::SerializeMap, S::Error> {
+        self.begin(len)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        self.begin(Some(len)).map(ObjectStructSerializer)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<S::Ok, S::Error> {
+        not_an_object("a bool")
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<S::Ok, S::Error> {
+        not_an_object("a number")
+    }
+
+    fn serialize_char(self, _: char) -> Result<S::Ok, S::Error> {
+        not_an_object("a string")
+    }
+
+    fn serialize_str(self, _: &str) -> Result<S::Ok, S::Error> {
+        not_an_object("a string")
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<S::Ok, S::Error> {
+        not_an_object("bytes")
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        not_an_object("none")
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<S::Ok, S::Error> {
+        not_an_object("an option")
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        not_an_object("a unit")
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        not_an_object(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        not_an_object(name)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<S::Ok, S::Error> {
+        not_an_object(name)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        not_an_object("a sequence")
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, S::Error> {
+        not_an_object("a tuple")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        not_an_object(name)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        not_an_object(name)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        not_an_object(name)
+    }
+}
+
+/// Writes the fields of a struct as entries of the map of its object.
+pub(super) struct ObjectStructSerializer<M>(M);
+
+impl<M: SerializeMap> SerializeStruct for ObjectStructSerializer<M> {
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<M::Ok, M::Error> {
+        self.0.end()
+    }
+}
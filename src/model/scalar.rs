@@ -0,0 +1,188 @@
+//! (de)serialization for strings and references, which are written bare in canonical form.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Error, IntoDeserializer, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::form::{self, Form};
+use super::value::is_zid;
+use super::{Reference, TypedReference, TypedReferenceType, ZString};
+use crate::label::{self, ZLabel};
+
+impl Serialize for ZString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let canonical = form::current() == Form::Canonical;
+        if canonical && !is_zid(&self.value) {
+            return serializer.serialize_str(&self.value);
+        }
+
+        // strings that look like references keep their type in canonical form.
+        let mut map = serializer.serialize_map(Some(1 + usize::from(canonical)))?;
+        if canonical {
+            map.serialize_entry("Z1K1", &label::Z6::VAL)?;
+        }
+        map.serialize_entry("Z6K1", &self.value)?;
+        map.end()
+    }
+}
+
+struct ZStringVisitor;
+
+impl<'de> Visitor<'de> for ZStringVisitor {
+    type Value = ZString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a Z6/string")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<ZString, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: Error>(self, value: String) -> Result<ZString, E> {
+        Ok(ZString { value })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<ZString, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "Z1K1" => {
+                    map.next_value::<label::Z6>()?;
+                }
+                "Z6K1" if value.is_some() => return Err(A::Error::duplicate_field("Z6K1")),
+                "Z6K1" => value = Some(map.next_value()?),
+                _ => return Err(A::Error::unknown_field(&key, &["Z1K1", "Z6K1"])),
+            }
+        }
+        let value = value.ok_or_else(|| A::Error::missing_field("Z6K1"))?;
+        Ok(ZString { value })
+    }
+}
+
+impl<'de> Deserialize<'de> for ZString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ZStringVisitor)
+    }
+}
+
+impl<Id: Serialize> Serialize for Reference<Id> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if form::current() == Form::Canonical {
+            return self.id.serialize(serializer);
+        }
+
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("Z9K1", &self.id)?;
+        map.end()
+    }
+}
+
+struct ReferenceVisitor<Id>(PhantomData<Id>);
+
+impl<'de, Id: Deserialize<'de>> Visitor<'de> for ReferenceVisitor<Id> {
+    type Value = Reference<Id>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a Z9/reference")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Reference<Id>, E> {
+        let id = Id::deserialize(v.into_deserializer())?;
+        Ok(Reference { id })
+    }
+
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Reference<Id>, E> {
+        let id = Id::deserialize(serde::de::value::BorrowedStrDeserializer::new(v))?;
+        Ok(Reference { id })
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Reference<Id>, E> {
+        let id = Id::deserialize(v.into_deserializer())?;
+        Ok(Reference { id })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Reference<Id>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut id = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match &*key {
+                "Z1K1" => {
+                    map.next_value::<label::Z9>()?;
+                }
+                "Z9K1" if id.is_some() => return Err(A::Error::duplicate_field("Z9K1")),
+                "Z9K1" => id = Some(map.next_value()?),
+                _ => return Err(A::Error::unknown_field(&key, &["Z1K1", "Z9K1"])),
+            }
+        }
+        let id = id.ok_or_else(|| A::Error::missing_field("Z9K1"))?;
+        Ok(Reference { id })
+    }
+}
+
+impl<'de, Id: Deserialize<'de>> Deserialize<'de> for Reference<Id> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ReferenceVisitor(PhantomData))
+    }
+}
+
+impl<Type> Serialize for TypedReferenceType<Type> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        label::Z9::VAL.serialize(serializer)
+    }
+}
+
+impl<'de, Type> Deserialize<'de> for TypedReferenceType<Type> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        label::Z9::deserialize(deserializer)?;
+        Ok(TypedReferenceType(PhantomData))
+    }
+}
+
+impl<Type> Serialize for TypedReference<Type> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Reference { id: &self.id }.serialize(serializer)
+    }
+}
+
+impl<'de, Type> Deserialize<'de> for TypedReference<Type> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Reference { id } = Reference::deserialize(deserializer)?;
+        Ok(TypedReference {
+            id,
+            ty: PhantomData,
+        })
+    }
+}
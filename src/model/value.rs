@@ -0,0 +1,794 @@
+//! A dynamic, untyped representation of Z1/objects.
+//!
+//! [`ZValue`] can hold any ZObject without knowing its shape at compile time.
+//! It can be converted to and from the typed [`Object`] wrappers, so tools can
+//! inspect an unknown object first and downcast it afterwards.
+
+use std::fmt;
+
+use serde::de::value::MapDeserializer;
+use serde::de::{IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Impossible, SerializeMap, SerializeSeq, SerializeStruct};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::form::{self, Form};
+use super::list::grow_stack;
+use super::object::ZType;
+use super::{Object, Reference, ZObject};
+use crate::label::{self, ZLabel};
+
+/// Returns whether `s` is a ZID such as `Z6` or `Z1234`.
+pub fn is_zid(s: &str) -> bool {
+    let Some(digits) = s.strip_prefix('Z') else {
+        return false;
+    };
+    !digits.starts_with('0') && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Any Z1/object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZValue {
+    /// A Z6/string.
+    String(String),
+    /// A Z9/reference to a ZID.
+    Reference(String),
+    /// Any other object. The `Z1K1` key holds the type of the object.
+    Object(ZMap),
+}
+
+impl ZValue {
+    /// The type of this value, i.e. its `Z1K1`.
+    pub fn ty(&self) -> Option<&ZValue> {
+        match self {
+            ZValue::Object(map) => map.get("Z1K1"),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of a key of this object.
+    pub fn get(&self, key: &str) -> Option<&ZValue> {
+        self.as_object()?.get(key)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ZValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<&str> {
+        match self {
+            ZValue::Reference(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&ZMap> {
+        match self {
+            ZValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Converts a typed object into a `ZValue`.
+    pub fn from_object<T: ZObject + Serialize>(obj: &Object<T>) -> Result<ZValue, Error> {
+        form::with_form(Form::Normal, || obj.serialize(ZValueSerializer))
+    }
+
+    /// The type of a Z881/typed list with elements of type `elem`.
+    pub fn list_type(elem: ZValue) -> ZValue {
+        ZValue::Object(ZMap {
+            entries: vec![
+                ("Z1K1".to_owned(), ZValue::Reference("Z7".to_owned())),
+                ("Z7K1".to_owned(), ZValue::Reference("Z881".to_owned())),
+                ("Z881K1".to_owned(), elem),
+            ],
+        })
+    }
+
+    /// Builds a Z881/typed list with elements of type `elem`.
+    pub fn list(elem: ZValue, items: impl IntoIterator<Item = ZValue>) -> ZValue {
+        let ty = ZValue::list_type(elem);
+        let items: Vec<_> = items.into_iter().collect();
+        let mut list = ZValue::Object(ZMap {
+            entries: vec![("Z1K1".to_owned(), ty.clone())],
+        });
+        for item in items.into_iter().rev() {
+            list = ZValue::Object(ZMap {
+                entries: vec![
+                    ("Z1K1".to_owned(), ty.clone()),
+                    ("K1".to_owned(), item),
+                    ("K2".to_owned(), list),
+                ],
+            });
+        }
+        list
+    }
+
+    /// The type of a Z882/typed pair with elements of types `first` and `second`.
+    pub fn pair_type(first: ZValue, second: ZValue) -> ZValue {
+        ZValue::Object(ZMap {
+            entries: vec![
+                ("Z1K1".to_owned(), ZValue::Reference("Z7".to_owned())),
+                ("Z7K1".to_owned(), ZValue::Reference("Z882".to_owned())),
+                ("Z882K1".to_owned(), first),
+                ("Z882K2".to_owned(), second),
+            ],
+        })
+    }
+
+    /// Builds a Z882/typed pair of `first` and `second`, whose types are
+    /// `first_type` and `second_type`.
+    pub fn pair(first_type: ZValue, second_type: ZValue, first: ZValue, second: ZValue) -> ZValue {
+        ZValue::Object(ZMap {
+            entries: vec![
+                (
+                    "Z1K1".to_owned(),
+                    ZValue::pair_type(first_type, second_type),
+                ),
+                ("K1".to_owned(), first),
+                ("K2".to_owned(), second),
+            ],
+        })
+    }
+
+    /// The type of a Z883/typed map from `key` to `value`.
+    pub fn map_type(key: ZValue, value: ZValue) -> ZValue {
+        ZValue::Object(ZMap {
+            entries: vec![
+                ("Z1K1".to_owned(), ZValue::Reference("Z7".to_owned())),
+                ("Z7K1".to_owned(), ZValue::Reference("Z883".to_owned())),
+                ("Z883K1".to_owned(), key),
+                ("Z883K2".to_owned(), value),
+            ],
+        })
+    }
+
+    /// Builds a Z883/typed map from keys of type `key_type` to values of type
+    /// `value_type`, keeping the entries in order.
+    pub fn map(
+        key_type: ZValue,
+        value_type: ZValue,
+        entries: impl IntoIterator<Item = (ZValue, ZValue)>,
+    ) -> ZValue {
+        let pair_type = ZValue::pair_type(key_type.clone(), value_type.clone());
+        let pairs = entries
+            .into_iter()
+            .map(|(key, value)| ZValue::pair(key_type.clone(), value_type.clone(), key, value));
+        let list = ZValue::list(pair_type, pairs);
+        ZValue::Object(ZMap {
+            entries: vec![
+                ("Z1K1".to_owned(), ZValue::map_type(key_type, value_type)),
+                ("K1".to_owned(), list),
+            ],
+        })
+    }
+
+    /// If this is a Z881/typed list, returns its element type and its elements.
+    pub fn as_list(&self) -> Option<(&ZValue, Vec<&ZValue>)> {
+        let ty = self.ty()?;
+        if ty.ty()?.as_reference()? != "Z7" || ty.get("Z7K1")?.as_reference()? != "Z881" {
+            return None;
+        }
+        let elem = ty.get("Z881K1")?;
+
+        let mut items = Vec::new();
+        let mut list = self.as_object()?;
+        loop {
+            match (list.len(), list.get("K1"), list.get("K2")) {
+                (1, None, None) => return Some((elem, items)),
+                (3, Some(head), Some(tail)) => {
+                    items.push(head);
+                    list = tail.as_object()?;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Downcasts this value into a typed object.
+    pub fn into_object<T: ZObject + for<'de> Deserialize<'de>>(self) -> Result<Object<T>, Error> {
+        Object::deserialize(self)
+    }
+
+    /// Builds a value from the entries of a map, recognizing the
+    /// `{"Z1K1": "Z6", "Z6K1": ...}` and `{"Z1K1": "Z9", "Z9K1": ...}` forms.
+    fn from_map(mut map: ZMap) -> ZValue {
+        if map.len() == 2 {
+            for (ty, key) in [("Z6", "Z6K1"), ("Z9", "Z9K1")] {
+                if map.get("Z1K1").and_then(ZValue::as_reference) != Some(ty) {
+                    continue;
+                }
+                let value = match map.remove(key) {
+                    Some(ZValue::String(s) | ZValue::Reference(s)) => s,
+                    Some(other) => {
+                        map.insert(key.to_owned(), other);
+                        continue;
+                    }
+                    None => continue,
+                };
+                return if ty == "Z6" {
+                    ZValue::String(value)
+                } else {
+                    ZValue::Reference(value)
+                };
+            }
+        }
+        ZValue::Object(map)
+    }
+
+    /// Interprets a bare string the way it would be read from JSON.
+    fn from_bare(s: String) -> ZValue {
+        if is_zid(&s) {
+            ZValue::Reference(s)
+        } else {
+            ZValue::String(s)
+        }
+    }
+}
+
+impl<T: ZObject + Serialize> TryFrom<&'_ Object<T>> for ZValue {
+    type Error = Error;
+    fn try_from(value: &Object<T>) -> Result<Self, Self::Error> {
+        ZValue::from_object(value)
+    }
+}
+
+impl<T: ZObject + for<'de> Deserialize<'de>> TryFrom<ZValue> for Object<T> {
+    type Error = Error;
+    fn try_from(value: ZValue) -> Result<Self, Self::Error> {
+        value.into_object()
+    }
+}
+
+impl From<String> for ZValue {
+    fn from(s: String) -> ZValue {
+        ZValue::String(s)
+    }
+}
+
+impl From<&str> for ZValue {
+    fn from(s: &str) -> ZValue {
+        ZValue::String(s.to_owned())
+    }
+}
+
+impl From<ZMap> for ZValue {
+    fn from(map: ZMap) -> ZValue {
+        ZValue::from_map(map)
+    }
+}
+
+impl ZObject for ZValue {
+    type ZType = AnyType;
+    const SELF_TYPED: bool = true;
+}
+
+/// The type of a [`ZValue`], which can be any Z1/object.
+#[derive(Debug, Default)]
+pub struct AnyType;
+
+impl ZType for AnyType {
+    type Value = AnyTypeValue;
+}
+
+impl Serialize for AnyType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        label::Z1::VAL.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IgnoredAny::deserialize(deserializer)?;
+        Ok(AnyType)
+    }
+}
+
+/// [`AnyType`] used as a value, such as in the header of a list of [`ZValue`]s. It
+/// is written as a reference to Z1, but like `AnyType`, any type is accepted.
+#[derive(Debug, Default)]
+pub struct AnyTypeValue;
+
+impl Serialize for AnyTypeValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Object::<Reference<label::Z1>>::default().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyTypeValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IgnoredAny::deserialize(deserializer)?;
+        Ok(AnyTypeValue)
+    }
+}
+
+/// The keys of an object, in the order they were inserted.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ZMap {
+    entries: Vec<(String, ZValue)>,
+}
+
+impl ZMap {
+    pub fn new() -> ZMap {
+        ZMap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ZValue> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut ZValue> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Inserts a key. If the key already exists, its value is replaced in place
+    /// and the old value is returned.
+    pub fn insert(&mut self, key: String, value: ZValue) -> Option<ZValue> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(std::mem::replace(old, value));
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<ZValue> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(pos).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ZValue)> {
+        self.entries.iter().map(|(k, v)| (&**k, v))
+    }
+}
+
+impl FromIterator<(String, ZValue)> for ZMap {
+    fn from_iter<I: IntoIterator<Item = (String, ZValue)>>(iter: I) -> Self {
+        let mut map = ZMap::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl Extend<(String, ZValue)> for ZMap {
+    fn extend<I: IntoIterator<Item = (String, ZValue)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl IntoIterator for ZMap {
+    type Item = (String, ZValue);
+    type IntoIter = std::vec::IntoIter<(String, ZValue)>;
+    fn into_iter(mut self) -> Self::IntoIter {
+        std::mem::take(&mut self.entries).into_iter()
+    }
+}
+
+impl Drop for ZMap {
+    fn drop(&mut self) {
+        // lists in normal form nest once per element, so dropping the values of
+        // a map recursively could run out of stack. Take the entries of nested
+        // maps out before they are dropped, so that each map is empty by then.
+        let mut values: Vec<_> = std::mem::take(&mut self.entries);
+        while let Some((_, value)) = values.pop() {
+            if let ZValue::Object(mut map) = value {
+                values.append(&mut map.entries);
+            }
+        }
+    }
+}
+
+/// Errors from converting between [`ZValue`]s and typed objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+// (de)serialization of `ZValue`s. Either form is accepted when deserializing, and
+// values are serialized in the current form of the `form` module.
+
+impl Serialize for ZValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if form::current() == Form::Canonical {
+            match self {
+                ZValue::String(s) if !is_zid(s) => return serializer.serialize_str(s),
+                ZValue::Reference(id) => return serializer.serialize_str(id),
+                _ => {}
+            }
+            if let Some((elem, items)) = self.as_list() {
+                let mut seq = serializer.serialize_seq(Some(items.len() + 1))?;
+                seq.serialize_element(elem)?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                return seq.end();
+            }
+        }
+
+        match self {
+            ZValue::String(s) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("Z1K1", "Z6")?;
+                map.serialize_entry("Z6K1", s)?;
+                map.end()
+            }
+            ZValue::Reference(id) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("Z1K1", "Z9")?;
+                map.serialize_entry("Z9K1", id)?;
+                map.end()
+            }
+            ZValue::Object(obj) => {
+                let mut map = serializer.serialize_map(Some(obj.len()))?;
+                for (k, v) in obj.iter() {
+                    grow_stack(|| map.serialize_entry(k, v))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct ZValueVisitor;
+
+impl<'de> Visitor<'de> for ZValueVisitor {
+    type Value = ZValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad("a ZObject")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<ZValue, E> {
+        Ok(ZValue::from_bare(v.to_owned()))
+    }
+
+    fn visit_string<E: serde::de::Error>(self, v: String) -> Result<ZValue, E> {
+        Ok(ZValue::from_bare(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ZValue, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let Some(elem) = seq.next_element()? else {
+            return Err(serde::de::Error::custom(
+                "expected the element type of the list",
+            ));
+        };
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(ZValue::list(elem, items))
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<ZValue, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut map = ZMap::new();
+        while let Some((k, v)) = grow_stack(|| access.next_entry::<String, ZValue>())? {
+            if map.get(&k).is_some() {
+                return Err(serde::de::Error::custom(format_args!("duplicate key {k}")));
+            }
+            map.insert(k, v);
+        }
+        Ok(ZValue::from_map(map))
+    }
+}
+
+impl<'de> Deserialize<'de> for ZValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ZValueVisitor)
+    }
+}
+
+impl<'de> Deserializer<'de> for ZValue {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            ZValue::String(s) => {
+                let entries = [("Z1K1", "Z6".to_owned()), ("Z6K1", s)];
+                visitor.visit_map(MapDeserializer::new(entries.into_iter()))
+            }
+            ZValue::Reference(id) => {
+                let entries = [("Z1K1", "Z9".to_owned()), ("Z9K1", id)];
+                visitor.visit_map(MapDeserializer::new(entries.into_iter()))
+            }
+            ZValue::Object(map) => {
+                grow_stack(|| visitor.visit_map(MapDeserializer::new(map.into_iter())))
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ZValue {
+    type Deserializer = ZValue;
+    fn into_deserializer(self) -> ZValue {
+        self
+    }
+}
+
+/// A serializer that turns typed objects into [`ZValue`]s.
+pub struct ZValueSerializer;
+
+fn unsupported<T>(what: &str) -> Result<T, Error> {
+    Err(serde::ser::Error::custom(format_args!(
+        "{what} cannot be represented as a ZObject"
+    )))
+}
+
+impl Serializer for ZValueSerializer {
+    type Ok = ZValue;
+    type Error = Error;
+    type SerializeSeq = Impossible<ZValue, Error>;
+    type SerializeTuple = Impossible<ZValue, Error>;
+    type SerializeTupleStruct = Impossible<ZValue, Error>;
+    type SerializeTupleVariant = Impossible<ZValue, Error>;
+    type SerializeMap = ZMapSerializer;
+    type SerializeStruct = ZMapSerializer;
+    type SerializeStructVariant = Impossible<ZValue, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<ZValue, Error> {
+        Ok(ZValue::from_bare(v.to_owned()))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<ZMapSerializer, Error> {
+        Ok(ZMapSerializer {
+            map: ZMap {
+                entries: Vec::with_capacity(len.unwrap_or(0)),
+            },
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ZMapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<ZValue, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<ZValue, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<ZValue, Error> {
+        unsupported(name)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<ZValue, Error> {
+        unsupported("a bool")
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<ZValue, Error> {
+        unsupported("a number")
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<ZValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<ZValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<ZValue, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<ZValue, Error> {
+        unsupported("a number")
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<ZValue, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<ZValue, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<ZValue, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<ZValue, Error> {
+        unsupported("a number")
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<ZValue, Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<ZValue, Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<ZValue, Error> {
+        unsupported("a byte array")
+    }
+
+    fn serialize_none(self) -> Result<ZValue, Error> {
+        unsupported("a missing value")
+    }
+
+    fn serialize_unit(self) -> Result<ZValue, Error> {
+        unsupported("a unit")
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<ZValue, Error> {
+        unsupported(name)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<ZValue, Error> {
+        unsupported(name)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        unsupported("a sequence")
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+        unsupported("a tuple")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        unsupported(name)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        unsupported(name)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        unsupported(name)
+    }
+}
+
+#[doc(hidden)]
+pub struct ZMapSerializer {
+    map: ZMap,
+    key: Option<String>,
+}
+
+impl SerializeMap for ZMapSerializer {
+    type Ok = ZValue;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(ZValueSerializer)? {
+            ZValue::String(k) | ZValue::Reference(k) => {
+                self.key = Some(k);
+                Ok(())
+            }
+            ZValue::Object(_) => unsupported("an object key"),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.map.insert(key, value.serialize(ZValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<ZValue, Error> {
+        Ok(ZValue::from_map(self.map))
+    }
+}
+
+impl SerializeStruct for ZMapSerializer {
+    type Ok = ZValue;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.map
+            .insert(key.to_owned(), value.serialize(ZValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<ZValue, Error> {
+        Ok(ZValue::from_map(self.map))
+    }
+}
@@ -1,8 +1,16 @@
-use serde_json::{from_str, to_string_pretty};
+use serde::Deserialize;
+use serde_json::{from_str, json, to_string_pretty, to_value};
 use std::error::Error;
 
+use crate::label::{self, ZLabel};
+use crate::model::error::ErrorType;
+use crate::model::form::{self, Canonical, Form};
 use crate::model::list::TypedList;
-use crate::model::{Object, Pair, ZString, ZUnit};
+use crate::model::{
+    Argument, Boolean, Float64, Function, Integer, KeyDeclaration, MonolingualText,
+    MultilingualText, NaturalLanguage, NaturalNumber, Object, Pair, Persistent, Reference, Sign,
+    TypeDefinition, TypedMap, TypedPair, Void, ZError, ZObject, ZString, ZUnit, ZValue,
+};
 
 #[test]
 pub fn serialize_list() -> Result<(), Box<dyn Error>> {
@@ -42,9 +50,793 @@ pub fn deserialize() -> Result<(), Box<dyn Error>> {
         }    
     }"#;
 
-    let obj: Object<Pair<ZString, ZUnit>> = from_str(json)?;
+    let obj: Object<Pair<ZString, Void>> = from_str(json)?;
 
     dbg!(obj);
 
     Ok(())
 }
+
+#[test]
+pub fn dynamic_value_roundtrip() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/full_eval.json");
+
+    let value: ZValue = from_str(json)?;
+    assert_eq!(
+        value.ty(),
+        Some(&ZValue::Reference("Z7".into())),
+        "a function call"
+    );
+    assert_eq!(
+        serde_json::to_value(&value)?,
+        from_str::<serde_json::Value>(json)?
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn dynamic_value_downcast() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/list_string.json");
+
+    let value: ZValue = from_str(json)?;
+    let list: Object<TypedList<ZString>> = value.clone().try_into()?;
+    let strings: Vec<_> = list.value.inner.iter().map(|s| &*s.value).collect();
+    assert_eq!(strings, ["1", "2"]);
+
+    assert_eq!(ZValue::from_object(&list)?, value);
+
+    let string = ZValue::from_object(&Object::new(ZString::from("hello")))?;
+    assert_eq!(string, ZValue::String("hello".into()));
+    assert!(string.into_object::<TypedList<ZString>>().is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn canonical_list() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/list_string.json");
+
+    let o: Object<TypedList<ZString>> = from_str(json)?;
+    let canonical = to_value(Canonical(&o))?;
+    assert_eq!(canonical, json!(["Z6", "1", "2"]));
+
+    let o: Object<TypedList<ZString>> = serde_json::from_value(canonical)?;
+    assert_eq!(to_value(&o)?, from_str::<serde_json::Value>(json)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn canonical_object() -> Result<(), Box<dyn Error>> {
+    let language = Object::new(NaturalLanguage {
+        code: "en".into(),
+        code_aliases: TypedList { inner: vec![] },
+    });
+
+    let canonical = to_value(Canonical(&language))?;
+    assert_eq!(
+        canonical,
+        json!({
+            "Z1K1": "Z60",
+            "Z60K1": "en",
+            "Z60K2": ["Z6"],
+        })
+    );
+
+    let normal = to_value(&language)?;
+    assert_eq!(normal["Z60K1"], json!({ "Z1K1": "Z6", "Z6K1": "en" }));
+    assert_eq!(
+        normal["Z60K2"]["Z1K1"]["Z881K1"],
+        json!({ "Z1K1": "Z9", "Z9K1": "Z6" })
+    );
+
+    let from_canonical: Object<NaturalLanguage> = serde_json::from_value(canonical)?;
+    assert_eq!(to_value(&from_canonical)?, normal);
+
+    let text = Object::new(MonolingualText {
+        language: Reference { id: "Z1002".into() },
+        text: "Z1000K1".into(),
+    });
+    assert_eq!(
+        to_value(Canonical(&text))?,
+        json!({
+            "Z1K1": "Z11",
+            "Z11K1": "Z1002",
+            "Z11K2": "Z1000K1",
+        })
+    );
+
+    // strings that look like ZIDs keep their type in canonical form.
+    let zid = Object::new(ZString::from("Z6"));
+    assert_eq!(
+        to_value(Canonical(&zid))?,
+        json!({ "Z1K1": "Z6", "Z6K1": "Z6" })
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn convert_forms() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/full_eval.json");
+    let normal: serde_json::Value = from_str(json)?;
+
+    let canonical = form::convert(Form::Canonical, &normal, serde_json::value::Serializer)?;
+    assert_eq!(canonical["Z1000K1"], json!("5"));
+    assert_eq!(canonical["Z7K1"]["Z8K2"], json!("Z6"));
+    assert_eq!(canonical["Z7K1"]["Z8K3"], json!(["Z20"]));
+    assert_eq!(canonical["Z7K1"]["Z8K1"][0], json!("Z17"));
+    assert_eq!(canonical["Z7K1"]["Z8K1"][2]["Z17K2"], json!("Z1000K2"));
+
+    let back = form::convert(Form::Normal, &canonical, serde_json::value::Serializer)?;
+    assert_eq!(back, normal);
+
+    Ok(())
+}
+
+#[test]
+pub fn deserialize_list_any_key_order() -> Result<(), Box<dyn Error>> {
+    let ty = json!({
+        "Z1K1": { "Z1K1": "Z9", "Z9K1": "Z7" },
+        "Z7K1": { "Z1K1": "Z9", "Z9K1": "Z881" },
+        "Z881K1": { "Z1K1": "Z9", "Z9K1": "Z6" },
+    });
+    let json = json!({
+        "K2": {
+            "K2": { "Z1K1": ty },
+            "K1": { "Z6K1": "2", "Z1K1": "Z6" },
+            "Z1K1": ty,
+        },
+        "K1": { "Z1K1": "Z6", "Z6K1": "1" },
+        "Z1K1": ty,
+    });
+
+    let o: Object<TypedList<ZString>> = serde_json::from_value(json)?;
+    let strings: Vec<_> = o.value.inner.iter().map(|s| &*s.value).collect();
+    assert_eq!(strings, ["1", "2"]);
+
+    let error = |json| {
+        serde_json::from_value::<Object<TypedList<ZString>>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    let one = json!({ "Z1K1": "Z6", "Z6K1": "1" });
+    let empty = json!({ "Z1K1": ty });
+    assert_eq!(
+        error(json!({ "Z1K1": ty, "K1": one })),
+        "missing field `K2`"
+    );
+    assert_eq!(
+        error(json!({ "Z1K1": ty, "K2": empty })),
+        "missing field `K1`"
+    );
+    assert_eq!(
+        error(json!({ "Z1K1": ty, "K1": one, "K2": {} })),
+        "missing field `Z1K1`"
+    );
+    assert!(error(json!({ "Z1K1": ty, "K3": one })).starts_with("unknown field `K3`"));
+
+    let duplicate = format!(r#"{{ "Z1K1": {ty}, "K1": {one}, "K1": {one}, "K2": {empty} }}"#);
+    let error = from_str::<Object<TypedList<ZString>>>(&duplicate).unwrap_err();
+    assert!(error.to_string().starts_with("duplicate field `K1`"));
+
+    Ok(())
+}
+
+#[test]
+pub fn deserialize_object_any_key_order() -> Result<(), Box<dyn Error>> {
+    let json = r#"{
+        "Z22K2": { "Z9K1": "Z24", "Z1K1": "Z9" },
+        "Z22K1": { "Z6K1": "13", "Z1K1": "Z6" },
+        "Z1K1": { "Z9K1": "Z22", "Z1K1": "Z9" }
+    }"#;
+    let obj: Object<Pair<ZString, Void>> = from_str(json)?;
+    assert_eq!(obj.value.left.value, "13");
+
+    let error = |json| {
+        from_str::<Object<Pair<ZString, Void>>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    let missing = r#"{ "Z1K1": "Z22", "Z22K1": "13" }"#;
+    assert!(error(missing).starts_with("missing field `Z22K2`"));
+    let missing = r#"{ "Z22K1": "13", "Z22K2": "Z24" }"#;
+    assert!(error(missing).starts_with("missing field `Z1K1`"));
+    let duplicate = r#"{ "Z1K1": "Z22", "Z22K1": "13", "Z22K1": "14", "Z22K2": "Z24" }"#;
+    assert!(error(duplicate).starts_with("duplicate field `Z22K1`"));
+    let duplicate = r#"{ "Z1K1": "Z22", "Z1K1": "Z22", "Z22K1": "13", "Z22K2": "Z24" }"#;
+    assert!(error(duplicate).starts_with("duplicate field `Z1K1`"));
+
+    Ok(())
+}
+
+#[test]
+pub fn long_list() -> Result<(), Box<dyn Error>> {
+    const LEN: usize = 100_000;
+    let list = Object::new(TypedList::<ZString> {
+        inner: (0..LEN).map(|i| ZString::from(&*i.to_string())).collect(),
+    });
+    let check = |o: Object<TypedList<ZString>>| {
+        assert_eq!(o.value.inner.len(), LEN);
+        assert_eq!(o.value.inner[LEN - 1].value, (LEN - 1).to_string());
+    };
+
+    let normal = serde_json::to_string(&list)?;
+    let mut de = serde_json::Deserializer::from_str(&normal);
+    de.disable_recursion_limit();
+    check(Object::deserialize(&mut de)?);
+
+    let canonical = serde_json::to_string(&Canonical(&list))?;
+    check(from_str(&canonical)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn long_list_value() -> Result<(), Box<dyn Error>> {
+    const LEN: usize = 100_000;
+    let list = Object::new(TypedList::<ZString> {
+        inner: (0..LEN).map(|i| ZString::from(&*i.to_string())).collect(),
+    });
+    let len = |value: &ZValue| value.as_list().map(|(_, items)| items.len());
+
+    let normal = serde_json::to_string(&list)?;
+    let mut de = serde_json::Deserializer::from_str(&normal);
+    de.disable_recursion_limit();
+    let value = ZValue::deserialize(&mut de)?;
+    assert_eq!(len(&value), Some(LEN));
+
+    let mut canonical = Vec::new();
+    let mut de = serde_json::Deserializer::from_str(&normal);
+    de.disable_recursion_limit();
+    form::convert(
+        Form::Canonical,
+        &mut de,
+        &mut serde_json::Serializer::new(&mut canonical),
+    )?;
+    let mut normal_again = Vec::new();
+    form::convert(
+        Form::Normal,
+        &mut serde_json::Deserializer::from_slice(&canonical),
+        &mut serde_json::Serializer::new(&mut normal_again),
+    )?;
+    assert_eq!(normal_again, normal.as_bytes());
+
+    let from_object = ZValue::from_object(&list)?;
+    assert_eq!(len(&from_object), Some(LEN));
+    let typed: Object<TypedList<ZString>> = value.into_object()?;
+    assert_eq!(typed.value.inner.len(), LEN);
+
+    Ok(())
+}
+
+#[test]
+pub fn deserialize_borrowed() -> Result<(), Box<dyn Error>> {
+    let json = r#"{
+        "Z1K1": { "Z1K1": "Z9", "Z9K1": "Z22" },
+        "Z22K1": { "Z1K1": "Z9", "Z9K1": "Z41" },
+        "Z22K2": "Z24"
+    }"#;
+    let obj: Object<Pair<Reference<&str>, Reference<&str>>> = from_str(json)?;
+    assert_eq!(obj.value.left.id, "Z41");
+    assert_eq!(obj.value.right.id, "Z24");
+
+    Ok(())
+}
+
+#[test]
+pub fn serialize_type_first() -> Result<(), Box<dyn Error>> {
+    let obj = Object::new(Pair {
+        left: ZString::from("13"),
+        right: Void::default(),
+    });
+    assert_eq!(
+        serde_json::to_string(&obj)?,
+        r#"{"Z1K1":{"Z1K1":"Z9","Z9K1":"Z22"},"Z22K1":{"Z1K1":"Z6","Z6K1":"13"},"Z22K2":{"Z1K1":"Z9","Z9K1":"Z24"}}"#
+    );
+
+    Ok(())
+}
+
+/// A type that is not part of the model, such as one defined by users of the crate.
+#[derive(ZObject, Debug)]
+#[zobject(Z12345)]
+struct Custom<T: ZObject> {
+    #[zobject(K1)]
+    name: ZString,
+    #[zobject(K2)]
+    items: TypedList<T>,
+}
+
+#[test]
+pub fn derived_zobject() -> Result<(), Box<dyn Error>> {
+    let custom = Object::new(Custom {
+        name: "numbers".into(),
+        items: TypedList::<ZString> {
+            inner: vec!["1".into()],
+        },
+    });
+
+    let canonical = json!({
+        "Z1K1": "Z12345",
+        "Z12345K1": "numbers",
+        "Z12345K2": ["Z6", "1"],
+    });
+    assert_eq!(to_value(Canonical(&custom))?, canonical);
+
+    let normal = to_value(&custom)?;
+    assert_eq!(normal["Z1K1"], json!({ "Z1K1": "Z9", "Z9K1": "Z12345" }));
+    assert_eq!(
+        normal["Z12345K1"],
+        json!({ "Z1K1": "Z6", "Z6K1": "numbers" })
+    );
+
+    let from_canonical: Object<Custom<ZString>> = serde_json::from_value(canonical)?;
+    assert_eq!(to_value(&from_canonical)?, normal);
+
+    let error = |json| {
+        serde_json::from_value::<Object<Custom<ZString>>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(json!({ "Z1K1": "Z12345", "Z12345K1": "numbers" })),
+        "missing field `Z12345K2`"
+    );
+    assert!(error(json!({ "Z1K1": "Z12346", "Z12345K1": "numbers" }))
+        .starts_with("expected label Z12345, got Z12346"));
+    // unknown keys are rejected, like those of a typed list.
+    assert_eq!(
+        error(
+            json!({ "Z1K1": "Z12345", "Z12345K1": "numbers", "Z12345K2": ["Z6"], "Z12345K3": "x" })
+        ),
+        "unknown field `Z12345K3`, expected `Z12345K1` or `Z12345K2`"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn labels() -> Result<(), Box<dyn Error>> {
+    assert_eq!((label::Z6::LABEL, label::Z6::ID), ("Z6", 6));
+    assert_eq!((label::Z881::LABEL, label::Z881::ID), ("Z881", 881));
+    assert_eq!(label::Z::<12345>::LABEL, "Z12345");
+    assert_eq!(label::Z::<{ u32::MAX }>::LABEL, "Z4294967295");
+    assert_eq!(to_value(label::Z::<0>)?, "Z0");
+    assert_eq!(to_value(label::Z40)?, "Z40");
+    serde_json::from_value::<label::Z40>(json!("Z40"))?;
+    assert!(serde_json::from_value::<label::Z40>(json!("Z41")).is_err());
+    Ok(())
+}
+
+#[test]
+pub fn boolean() -> Result<(), Box<dyn Error>> {
+    let forms = [
+        json!("Z41"),
+        json!({ "Z1K1": "Z9", "Z9K1": "Z41" }),
+        json!({ "Z1K1": "Z40", "Z40K1": "Z41" }),
+        json!({
+            "Z1K1": { "Z1K1": "Z9", "Z9K1": "Z40" },
+            "Z40K1": { "Z1K1": "Z9", "Z9K1": "Z41" },
+        }),
+    ];
+    for json in forms {
+        let b: Object<Boolean> = serde_json::from_value(json)?;
+        assert!(b.value.value);
+    }
+
+    let f = Object::new(Boolean::from(false));
+    assert_eq!(
+        to_value(Canonical(&f))?,
+        json!({ "Z1K1": "Z40", "Z40K1": "Z42" })
+    );
+    let list = TypedList::<Boolean> {
+        inner: vec![true.into(), false.into()],
+    };
+    assert_eq!(
+        to_value(Canonical(Object::new(list)))?,
+        json!([
+            "Z40",
+            { "Z1K1": "Z40", "Z40K1": "Z41" },
+            { "Z1K1": "Z40", "Z40K1": "Z42" },
+        ])
+    );
+
+    assert!(serde_json::from_value::<Object<Boolean>>(json!("Z43")).is_err());
+    assert!(
+        serde_json::from_value::<Object<Boolean>>(json!({ "Z1K1": "Z6", "Z6K1": "Z41" })).is_err()
+    );
+    let error = |json| {
+        serde_json::from_value::<Object<Boolean>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error(json!({ "Z1K1": "Z40", "Z40K1": "Z41", "Z40K2": "Z41" })),
+        "unknown field `Z40K2`, expected `Z40K1` or `Z9K1`"
+    );
+    assert_eq!(
+        error(json!({ "Z1K1": "Z40", "Z40K1": "Z41", "Z9K1": "Z42" })),
+        "a boolean has either Z40K1 or Z9K1, not both"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn persistent() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/persistent_true.json");
+    let canonical: serde_json::Value = from_str(json)?;
+    let persistent: Object<Persistent<Boolean>> = from_str(json)?;
+    let persistent = &persistent.value;
+
+    assert_eq!(persistent.id.value, "Z41");
+    assert!(persistent.value.value);
+    let labels = &persistent.labels.texts.inner;
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels[1].language.id, "Z1430");
+    assert_eq!(labels[1].text.value, "wahr");
+    let aliases = &persistent.aliases.stringsets.inner[0];
+    assert_eq!(aliases.language.id, "Z1002");
+    assert_eq!(aliases.strings.inner.len(), 2);
+    let description = persistent.description.as_ref().unwrap();
+    assert_eq!(
+        description.texts.inner[0].text.value,
+        "The Boolean value true"
+    );
+
+    assert_eq!(to_value(Canonical(Object::new(persistent)))?, canonical);
+
+    // older persistent objects have no description.
+    let mut old = canonical.clone();
+    old.as_object_mut().unwrap().remove("Z2K5");
+    let persistent: Object<Persistent<Boolean>> = serde_json::from_value(old.clone())?;
+    assert!(persistent.value.description.is_none());
+    assert_eq!(to_value(Canonical(&persistent))?, old);
+
+    Ok(())
+}
+
+#[test]
+pub fn type_definition() -> Result<(), Box<dyn Error>> {
+    let json = include_str!("../test_data/type_boolean.json");
+    let canonical: serde_json::Value = from_str(json)?;
+    let persistent: Object<Persistent<TypeDefinition>> = from_str(json)?;
+    let ty = &persistent.value.value;
+
+    assert_eq!(ty.identity.id, "Z40");
+    let key = &ty.keys.inner[0];
+    assert_eq!(key.id.value, "Z40K1");
+    assert_eq!(key.ty.id, "Z40");
+    assert!(key.is_identity.is_some_and(bool::from));
+    assert_eq!(ty.validator.id, "Z140");
+    assert_eq!(ty.equality.as_ref().map(|r| &*r.id), Some("Z844"));
+    assert!(ty.renderer.is_none() && ty.parser.is_none());
+    assert_eq!(ty.deserialisers.as_ref().unwrap().inner[0].id, "Z20028");
+    assert_eq!(ty.serialisers.as_ref().unwrap().inner[0].id, "Z20030");
+
+    // keys that were left out are not written.
+    assert_eq!(to_value(Canonical(&persistent))?, canonical);
+    let normal = to_value(&persistent)?;
+    assert_eq!(
+        normal["Z2K2"]["Z4K7"]["Z1K1"]["Z881K1"],
+        json!({ "Z1K1": "Z9", "Z9K1": "Z46" })
+    );
+    assert_eq!(
+        normal["Z2K2"]["Z4K7"]["K1"],
+        json!({ "Z1K1": "Z9", "Z9K1": "Z20028" })
+    );
+    let from_normal: Object<Persistent<TypeDefinition>> = serde_json::from_value(normal)?;
+    assert_eq!(to_value(Canonical(&from_normal))?, canonical);
+
+    // arguments can declare their type inline.
+    let argument: Object<Argument<TypeDefinition>> = Object::new(Argument {
+        ty: TypeDefinition {
+            identity: Reference { id: "Z40".into() },
+            keys: TypedList {
+                inner: vec![KeyDeclaration {
+                    ty: Reference { id: "Z40".into() },
+                    id: "Z40K1".into(),
+                    label: MultilingualText {
+                        texts: TypedList { inner: vec![] },
+                    },
+                    is_identity: None,
+                }],
+            },
+            validator: Reference { id: "Z140".into() },
+            equality: None,
+            renderer: None,
+            parser: None,
+            deserialisers: None,
+            serialisers: None,
+        },
+        key: "Z1000K1".into(),
+        label: MultilingualText {
+            texts: TypedList { inner: vec![] },
+        },
+    });
+    let canonical = to_value(Canonical(&argument))?;
+    assert_eq!(canonical["Z17K1"]["Z1K1"], json!("Z4"));
+    assert_eq!(canonical["Z17K1"]["Z4K2"][1].get("Z3K4"), None);
+    let from_canonical: Object<Argument<TypeDefinition>> = serde_json::from_value(canonical)?;
+    assert_eq!(to_value(&from_canonical)?, to_value(&argument)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn error() -> Result<(), Box<dyn Error>> {
+    let error = ZError::in_evaluation(
+        ZValue::Reference("Z10000".to_owned()),
+        ZError::generic("something went wrong"),
+    );
+    let canonical = json!({
+        "Z1K1": "Z5",
+        "Z5K1": "Z507",
+        "Z5K2": {
+            "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z885", "Z885K1": "Z507" },
+            "Z507K1": "Z10000",
+            "Z507K2": {
+                "Z1K1": "Z5",
+                "Z5K1": "Z500",
+                "Z5K2": {
+                    "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z885", "Z885K1": "Z500" },
+                    "Z500K1": "something went wrong",
+                },
+            },
+        },
+    });
+    let object = Object::new(error.clone());
+    assert_eq!(to_value(Canonical(&object))?, canonical);
+    assert_eq!(ZValue::from(error.clone()), ZValue::from_object(&object)?);
+
+    let from_canonical: Object<ZError> = serde_json::from_value(canonical)?;
+    assert_eq!(from_canonical.value, error);
+    assert_eq!(error.ty(), Some(ErrorType::ErrorInEvaluation));
+    assert_eq!(error.arg(1), Some(&ZValue::Reference("Z10000".to_owned())));
+    assert_eq!(
+        error.to_string(),
+        "Z507/error in evaluation: Z500/generic error: something went wrong"
+    );
+
+    let list = ZError::list([
+        ZError::zid_not_found("Z10001"),
+        ZError::not_implemented("maps"),
+    ]);
+    let (elem, items) = list.arg(1).unwrap().as_list().unwrap();
+    assert_eq!(elem, &ZValue::Reference("Z5".to_owned()));
+    assert_eq!(items.len(), 2);
+    let first: Object<ZError> = items[0].clone().into_object()?;
+    assert_eq!(first.value, ZError::zid_not_found("Z10001"));
+
+    Ok(())
+}
+
+#[test]
+pub fn dynamic_value_in_object() -> Result<(), Box<dyn Error>> {
+    let pair = Object::new(Pair {
+        left: ZValue::String("5".to_owned()),
+        right: ZValue::from(ZError::generic("oops")),
+    });
+    let canonical = to_value(Canonical(&pair))?;
+    assert_eq!(canonical["Z22K1"], json!("5"));
+    assert_eq!(canonical["Z22K2"]["Z5K1"], json!("Z500"));
+
+    let normal = to_value(&pair)?;
+    assert_eq!(normal["Z22K1"], json!({ "Z1K1": "Z6", "Z6K1": "5" }));
+    let from_normal: Object<Pair<ZValue, ZValue>> = serde_json::from_value(normal)?;
+    assert_eq!(from_normal.value.left, pair.value.left);
+    assert_eq!(from_normal.value.right, pair.value.right);
+
+    let list = Object::new(TypedList {
+        inner: vec![ZValue::Reference("Z41".to_owned()), "a".into()],
+    });
+    assert_eq!(to_value(Canonical(&list))?, json!(["Z1", "Z41", "a"]));
+    let from_canonical: Object<TypedList<ZValue>> =
+        serde_json::from_value(json!(["Z1", "Z41", "a"]))?;
+    assert_eq!(from_canonical.value.inner, list.value.inner);
+
+    Ok(())
+}
+
+#[test]
+pub fn typed_map() -> Result<(), Box<dyn Error>> {
+    let map: TypedMap<ZString, ZValue> = [
+        ("duration".into(), ZValue::from("1 ms")),
+        (
+            "implementation".into(),
+            ZValue::Reference("Z10001".to_owned()),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let map = Object::new(map);
+
+    let pair_type = json!({ "Z1K1": "Z7", "Z7K1": "Z882", "Z882K1": "Z6", "Z882K2": "Z1" });
+    let canonical = json!({
+        "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z883", "Z883K1": "Z6", "Z883K2": "Z1" },
+        "K1": [
+            pair_type,
+            { "Z1K1": pair_type, "K1": "duration", "K2": "1 ms" },
+            { "Z1K1": pair_type, "K1": "implementation", "K2": "Z10001" },
+        ],
+    });
+    assert_eq!(to_value(Canonical(&map))?, canonical);
+
+    let normal = to_value(&map)?;
+    assert_eq!(
+        normal["Z1K1"]["Z883K1"],
+        json!({ "Z1K1": "Z9", "Z9K1": "Z6" })
+    );
+    assert_eq!(
+        normal["K1"]["K1"]["K2"],
+        json!({ "Z1K1": "Z6", "Z6K1": "1 ms" })
+    );
+
+    let from_normal: Object<TypedMap<ZString, ZValue>> = serde_json::from_value(normal)?;
+    assert_eq!(to_value(Canonical(&from_normal))?, canonical);
+
+    let value = ZValue::map(
+        ZValue::Reference("Z6".to_owned()),
+        ZValue::Reference("Z1".to_owned()),
+        [
+            (ZValue::from("duration"), ZValue::from("1 ms")),
+            (
+                ZValue::from("implementation"),
+                ZValue::Reference("Z10001".to_owned()),
+            ),
+        ],
+    );
+    assert_eq!(value, ZValue::from_object(&map)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn typed_pair() -> Result<(), Box<dyn Error>> {
+    let pair = Object::new(TypedPair::from((ZString::from("a"), Boolean::from(true))));
+    let canonical = json!({
+        "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z882", "Z882K1": "Z6", "Z882K2": "Z40" },
+        "K1": "a",
+        "K2": { "Z1K1": "Z40", "Z40K1": "Z41" },
+    });
+    assert_eq!(to_value(Canonical(&pair))?, canonical);
+
+    let from_normal: Object<TypedPair<ZString, Boolean>> =
+        serde_json::from_value(to_value(&pair)?)?;
+    assert_eq!(to_value(Canonical(&from_normal))?, canonical);
+
+    let value = ZValue::pair(
+        ZValue::Reference("Z6".to_owned()),
+        ZValue::Reference("Z40".to_owned()),
+        ZValue::from("a"),
+        ZValue::from_object(&Object::new(Boolean::from(true)))?,
+    );
+    assert_eq!(value, ZValue::from_object(&pair)?);
+
+    Ok(())
+}
+
+#[test]
+pub fn numbers() -> Result<(), Box<dyn Error>> {
+    let integer = Object::new(Integer::from(-42));
+    let canonical = json!({
+        "Z1K1": "Z16683",
+        "Z16683K1": "Z16662",
+        "Z16683K2": { "Z1K1": "Z13518", "Z13518K1": "42" },
+    });
+    assert_eq!(to_value(Canonical(&integer))?, canonical);
+    let from_normal: Object<Integer> = serde_json::from_value(to_value(&integer)?)?;
+    assert_eq!(i64::try_from(&from_normal.value)?, -42);
+
+    for n in [i64::MIN, -1, 0, i64::MAX] {
+        assert_eq!(i64::try_from(&Integer::from(n))?, n);
+    }
+    let too_large = NaturalNumber {
+        value: "18446744073709551616".into(),
+    };
+    let error = u64::try_from(&too_large).unwrap_err();
+    assert_eq!(error.ty(), Some(ErrorType::ArgumentValueError));
+    assert_eq!(error.arg(1), Some(&ZValue::from("u64")));
+    assert_eq!(error.arg(2), Some(&ZValue::from(too_large)));
+    let error = i64::try_from(&Integer {
+        sign: Sign::Negative,
+        absolute_value: "9223372036854775809".into(),
+    })
+    .unwrap_err();
+    assert_eq!(error.ty(), Some(ErrorType::ArgumentValueError));
+    assert_eq!(error.arg(1), Some(&ZValue::from("i64")));
+
+    // zero, and only zero, has the neutral sign.
+    let ill_formed = [
+        (Sign::Neutral, "5"),
+        (Sign::Positive, "0"),
+        (Sign::Negative, "00"),
+    ];
+    for (sign, abs) in ill_formed {
+        let integer = Integer {
+            sign,
+            absolute_value: abs.into(),
+        };
+        let error = i64::try_from(&integer).unwrap_err();
+        assert_eq!(error.ty(), Some(ErrorType::NotWellFormed));
+        assert_eq!(error.arg(1), Some(&ZValue::from("Z16683/integer")));
+        assert_eq!(error.arg(2), Some(&ZValue::from(integer)));
+    }
+    let error = i64::try_from(&Integer {
+        sign: Sign::Positive,
+        absolute_value: "1e3".into(),
+    })
+    .unwrap_err();
+    assert_eq!(error.ty(), Some(ErrorType::NotWellFormed));
+    assert_eq!(error.arg(2), Some(&ZValue::from("1e3")));
+
+    let floats = [
+        0.0,
+        -0.0,
+        0.1,
+        -2.5,
+        f64::MAX,
+        f64::MIN_POSITIVE,
+        5e-324,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for f in floats {
+        let float = Object::new(Float64::from(f));
+        let float: Object<Float64> = serde_json::from_value(to_value(Canonical(&float))?)?;
+        assert_eq!(f64::try_from(&float.value)?.to_bits(), f.to_bits());
+    }
+    assert!(f64::try_from(&Float64::from(f64::NAN))?.is_nan());
+
+    let negative_zero = to_value(Canonical(&Object::new(Float64::from(-0.0))))?;
+    assert_eq!(negative_zero["Z20838K1"], "Z16662");
+    assert_eq!(negative_zero["Z20838K4"], "Z20826");
+
+    Ok(())
+}
+
+#[test]
+pub fn unit_and_void() -> Result<(), Box<dyn Error>> {
+    let unit = Object::new(ZUnit {});
+    assert_eq!(to_value(Canonical(&unit))?, json!({ "Z1K1": "Z21" }));
+    assert_eq!(
+        to_value(&unit)?,
+        json!({ "Z1K1": { "Z1K1": "Z9", "Z9K1": "Z21" } })
+    );
+    serde_json::from_value::<Object<ZUnit>>(json!({ "Z1K1": "Z21" }))?;
+    assert!(serde_json::from_value::<Object<ZUnit>>(json!("Z24")).is_err());
+
+    let void = Object::new(Void::default());
+    assert_eq!(to_value(Canonical(&void))?, json!("Z24"));
+    assert!(serde_json::from_value::<Object<Void>>(json!({ "Z1K1": "Z21" })).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn function_declaration() -> Result<(), Box<dyn Error>> {
+    let json: serde_json::Value = from_str(include_str!("../test_data/full_eval.json"))?;
+
+    // the lists of test cases and implementations are not lists of Z1s, but any
+    // list can be read as a list of `ZValue`s.
+    let function: Object<Function<ZValue, ZValue, ZValue, ZValue, ZValue>> =
+        serde_json::from_value(json["Z7K1"].clone())?;
+    let keys: Vec<_> = function
+        .value
+        .arguments
+        .inner
+        .iter()
+        .map(|argument| argument.key.value.as_str())
+        .collect();
+    assert_eq!(keys, ["Z1000K1", "Z1000K2"]);
+    assert_eq!(
+        function.value.arguments.inner[0].ty,
+        ZValue::Reference("Z6".into())
+    );
+    assert_eq!(function.value.return_type, ZValue::Reference("Z6".into()));
+    assert_eq!(function.value.implementations.inner.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! An on-disk cache of compiled implementations.
+//!
+//! A compiled cdylib is stored under a hash of everything that went into it: the
+//! source generated from the code string and `function_template.rs`, the target
+//! it was compiled for, the wfrt it was linked against and the version of rustc.
+//! As the way values are passed changes along with wfrt without a new version,
+//! it is the contents of `libwfrt.rlib` that are hashed. Entries are evicted
+//! least recently used first once there are too many of them or they take up
+//! too much space.
+//!
+//! The cache is configured with environment variables:
+//!
+//! * `WF_CACHE`: set to `off` to disable the cache.
+//! * `WF_CACHE_DIR`: where to keep entries, `target/wf-cache` by default.
+//! * `WF_CACHE_MAX_ENTRIES`: the number of entries to keep, 256 by default.
+//! * `WF_CACHE_MAX_SIZE`: the total size of entries to keep in bytes, 1 GiB by default.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+const DEFAULT_DIR: &str = "target/wf-cache";
+const DEFAULT_MAX_ENTRIES: usize = 256;
+const DEFAULT_MAX_SIZE: u64 = 1 << 30;
+
+pub struct Cache {
+    dir: PathBuf,
+    max_entries: usize,
+    max_size: u64,
+}
+
+fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok()?.parse().ok()
+}
+
+impl Cache {
+    /// The cache configured by the environment, or `None` if it is disabled.
+    pub fn from_env() -> Option<Cache> {
+        if env::var("WF_CACHE").is_ok_and(|v| v == "off") {
+            return None;
+        }
+        Some(Cache {
+            dir: env::var_os("WF_CACHE_DIR").map_or_else(|| DEFAULT_DIR.into(), PathBuf::from),
+            max_entries: var("WF_CACHE_MAX_ENTRIES").unwrap_or(DEFAULT_MAX_ENTRIES),
+            max_size: var("WF_CACHE_MAX_SIZE").unwrap_or(DEFAULT_MAX_SIZE),
+        })
+    }
+
+    /// The key of the cdylib compiled from `source` for `target`, linked against the
+    /// wfrt at `wfrt` with the given rustc.
+    pub fn key(source: &str, target: &str, wfrt: &Path, rustc_version: &str) -> io::Result<String> {
+        let wfrt = fs::read(wfrt)?;
+        let mut hasher = Sha256::new();
+        for part in [source.as_bytes(), target.as_bytes(), &wfrt, rustc_version.as_bytes()] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Ok(hasher.finalize().iter().fold(String::new(), |mut key, byte| {
+            let _ = write!(key, "{byte:02x}");
+            key
+        }))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// Links the entry for `key` to `dest`, replacing it, and returns whether there
+    /// was one. The entry's modification time is updated, so that it is evicted
+    /// last. Another evaluator can evict the entry at any time, so it is loaded
+    /// from `dest` rather than from the cache.
+    pub fn get(&self, key: &str, dest: &Path) -> bool {
+        let path = self.path(key);
+        let touched = File::options().write(true).open(&path).and_then(|f| f.set_modified(SystemTime::now()));
+        if touched.is_err() {
+            return false;
+        }
+        let _ = fs::remove_file(dest);
+        // the cache and `dest` can be on different file systems.
+        fs::hard_link(&path, dest).or_else(|_| fs::copy(&path, dest).map(drop)).is_ok()
+    }
+
+    /// Copies the compiled cdylib at `compiled` into the cache.
+    pub fn insert(&self, key: &str, compiled: &Path) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        // copied to a temporary file first, so that other evaluators never see
+        // half of an entry.
+        let tmp = NamedTempFile::new_in(&self.dir)?;
+        fs::copy(compiled, tmp.path())?;
+        tmp.persist(self.path(key))?;
+        self.evict()
+    }
+
+    /// Removes the least recently used entries until the cache is within its limits.
+    fn evict(&self) -> io::Result<()> {
+        let mut entries = Vec::new();
+        // entries can disappear while this runs if another evaluator is evicting too.
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            // temporary files from `insert` start with a dot.
+            if entry.file_name().to_str().is_none_or(|name| name.starts_with('.')) {
+                continue;
+            }
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else { continue };
+            let Ok(modified) = metadata.modified() else { continue };
+            entries.push((modified, metadata.len(), path));
+        }
+        entries.sort_by_key(|&(modified, _, _)| std::cmp::Reverse(modified));
+        let mut size = 0;
+        for (n, (_, len, path)) in entries.into_iter().enumerate() {
+            size += len;
+            // the newest entry is always kept, even if it is bigger than the limit.
+            if n > 0 && (n >= self.max_entries || size > self.max_size) {
+                match fs::remove_file(path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
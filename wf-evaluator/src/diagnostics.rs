@@ -0,0 +1,133 @@
+//! Describing compile errors and panics, pointing them at the code string
+//! rather than at the code generated from `function_template.rs`.
+
+use serde::Deserialize;
+use wikifunctions::model::error::ErrorType;
+use wikifunctions::model::{NaturalNumber, ZError};
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    message: String,
+    code: Option<DiagnosticCode>,
+    level: String,
+    spans: Vec<Span>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct Span {
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+    label: Option<String>,
+}
+
+/// The file name that rustc gives to code read from stdin.
+const GENERATED_FILE: &str = "<anon>";
+
+/// Where the code string is in the generated source. Lines and columns are
+/// 1-based, like those of rustc.
+pub struct CodeLocation {
+    line: usize,
+    column: usize,
+    lines: usize,
+}
+
+impl CodeLocation {
+    /// Finds `code` in `source`, which was generated from `function_template.rs`.
+    pub fn find(source: &str, code: &str) -> Option<CodeLocation> {
+        const BEGIN: &str = "// function begin\n";
+        let line_start = source.find(BEGIN)? + BEGIN.len();
+        let start = line_start + source[line_start..].find(code)?;
+        Some(CodeLocation {
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            lines: code.matches('\n').count() + 1,
+        })
+    }
+
+    /// Converts a line and column in the generated source to one in the code string.
+    fn map(&self, line: usize, column: usize) -> Option<(usize, usize)> {
+        if line < self.line || line >= self.line + self.lines {
+            return None;
+        }
+        let line = line - self.line + 1;
+        let column = if line == 1 { column.checked_sub(self.column - 1)? } else { column };
+        Some((line, column))
+    }
+}
+
+/// Builds a Z580/compile error, with the line and column of the diagnostic if it
+/// is in the code string.
+fn describe(diagnostic: &Diagnostic, location: Option<&CodeLocation>) -> ZError {
+    let mut message = match &diagnostic.code {
+        Some(code) => format!("{}[{}]: {}", diagnostic.level, code.code, diagnostic.message),
+        None => format!("{}: {}", diagnostic.level, diagnostic.message),
+    };
+    let Some(span) = diagnostic.spans.iter().find(|s| s.is_primary) else {
+        return ZError::new(ErrorType::CompileError).with_arg(1, message);
+    };
+    if let Some(label) = &span.label {
+        message = format!("{message} ({label})");
+    }
+    match location.and_then(|l| l.map(span.line_start, span.column_start)) {
+        Some((line, column)) => ZError::new(ErrorType::CompileError)
+            .with_arg(1, message)
+            .with_arg(2, NaturalNumber::from(line as u64))
+            .with_arg(3, NaturalNumber::from(column as u64)),
+        None => ZError::new(ErrorType::CompileError)
+            .with_arg(1, format!("in the code around the implementation: {message}")),
+    }
+}
+
+/// Builds the error for a failed compilation from the JSON diagnostics that
+/// rustc wrote to stderr. There is one Z580/compile error for each error
+/// diagnostic, collected into a Z509/list of errors if there are several.
+pub fn compile_error(stderr: &str, location: Option<&CodeLocation>) -> ZError {
+    let mut errors: Vec<_> = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Diagnostic>(line).ok())
+        .filter(|d| d.level.starts_with("error") && !d.message.starts_with("aborting due to"))
+        .map(|d| describe(&d, location))
+        .collect();
+    match errors.len() {
+        0 => ZError::new(ErrorType::CompileError).with_arg(1, "the implementation failed to compile"),
+        1 => errors.remove(0),
+        _ => ZError::list(errors),
+    }
+}
+
+/// Builds the Z585/panic error for a panic in the evaluated function, with its
+/// line and column if it is in the code string. The backtrace, if one was
+/// captured, is left without the frames of the template and the evaluator below
+/// the implementation.
+pub fn panic_error(panic: &wfrt::Panic, location: Option<&CodeLocation>) -> ZError {
+    let mut error = match &panic.location {
+        Some(l) if l.file == GENERATED_FILE => {
+            match location.and_then(|loc| loc.map(l.line as usize, l.column as usize)) {
+                Some((line, column)) => ZError::new(ErrorType::Panic)
+                    .with_arg(1, panic.message.as_str())
+                    .with_arg(2, NaturalNumber::from(line as u64))
+                    .with_arg(3, NaturalNumber::from(column as u64)),
+                None => ZError::new(ErrorType::Panic)
+                    .with_arg(1, format!("in the code around the implementation: {}", panic.message)),
+            }
+        }
+        Some(l) => ZError::new(ErrorType::Panic)
+            .with_arg(1, format!("at {}:{}:{}: {}", l.file, l.line, l.column, panic.message)),
+        None => ZError::new(ErrorType::Panic).with_arg(1, panic.message.as_str()),
+    };
+    if let Some(backtrace) = &panic.backtrace {
+        let mut frames = String::new();
+        for frame in backtrace.lines().take_while(|l| !l.contains("evaluate_inner")) {
+            frames.push_str(frame);
+            frames.push('\n');
+        }
+        error = error.with_arg(4, frames);
+    }
+    error
+}
@@ -1,5 +1,8 @@
 extern crate wfrt;
 
+#[global_allocator]
+static WF_ALLOCATOR: wfrt::Allocator = wfrt::Allocator;
+
 #[no_mangle]
 pub extern "C" fn evaluate_entrypoint(input: wfrt::ffi::Bytes<'_>) -> wfrt::ffi::OwnedBytes {{
     #[allow(non_snake_case)]
@@ -7,26 +10,35 @@ pub extern "C" fn evaluate_entrypoint(input: wfrt::ffi::Bytes<'_>) -> wfrt::ffi:
     {code}
     // function end
 
-    fn evaluate_inner(bytes: wfrt::ffi::Bytes<'_>) -> Result<wfrt::Value, String> {{
-        let values = wfrt::bytes_to_values(bytes).map_err(|e| e.to_string())?;
-        let result: Result<Result<wfrt::Value, String>, _> = std::panic::catch_unwind(move || {{
+    fn evaluate_inner(bytes: wfrt::ffi::Bytes<'_>) -> Result<wfrt::Value, wfrt::Failure> {{
+        let values = wfrt::bytes_to_values(bytes).map_err(|e| wfrt::Failure::Arguments(e.to_string()))?;
+        let result: Result<Result<wfrt::Value, wfrt::Failure>, _> = wfrt::catch_panic(move || {{
             let mut values = values.into_iter();
             #[allow(unused)]
-            fn get_next<T: TryFrom<wfrt::Value>>(values: &mut std::vec::IntoIter<wfrt::Value>) -> Result<T, String>
-            where
-                T::Error: ToString,
-            {{
-                values.next().ok_or_else(|| "out of values".to_owned()).and_then(|v| T::try_from(v).map_err(|e| e.to_string()))
+            fn get_next<T: TryFrom<wfrt::Value, Error = wfrt::ConversionError>>(values: &mut std::vec::IntoIter<wfrt::Value>, index: usize) -> Result<T, wfrt::Failure> {{
+                let value = values.next().ok_or_else(|| wfrt::Failure::Arguments("out of values".to_owned()))?;
+                T::try_from(value).map_err(|e| e.into_failure(index))
             }}
             let ret = {fn_name}({fn_args});
             Ok(wfrt::IntoValue::into_value(ret))
         }});
 
-        // TODO recover panic message
-        result.map_err(|_| "function panicked".to_owned())?
+        result.map_err(wfrt::Failure::Panic)?
     }}
 
     let result = evaluate_inner(input);
 
     wfrt::ffi::OwnedBytes::from_vec(wfrt::to_stdvec(&result).expect("serialization cannot fail"))
-}}
\ No newline at end of file
+}}
+
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn wf_alloc(len: usize) -> *mut u8 {{
+    wfrt::ffi::wasm::alloc(len)
+}}
+
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub unsafe extern "C" fn wf_evaluate(ptr: *mut u8, len: usize) -> u64 {{
+    wfrt::ffi::wasm::call(evaluate_entrypoint, ptr, len)
+}}
@@ -3,17 +3,43 @@
 //! Basically, the evaluator's job is to parse the input arguments,
 //! compile the program and run the program. For the input, we deserialize
 //! into a list of Values known to the evaluator. We then pass that to
-//! the compiled artifact by serializing it to bincode.
+//! the compiled artifact by serializing it to bincode. Anything that goes
+//! wrong along the way is reported as a Z5/error.
+//!
+//! Each input gets one Z22/evaluation result, written to stdout as a line of
+//! canonical JSON.
 
 use std::env::current_dir;
 use std::error::Error;
-use std::io::{stdin, BufReader, Write};
+use std::fmt;
+use std::io::{stdin, stdout, BufReader, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::time::Instant;
 
 use libloading::Library;
 use tempfile::NamedTempFile;
+use wikifunctions::model::error::ErrorType;
+use wikifunctions::model::form::Canonical;
+use wikifunctions::model::{ZError, ZValue};
+
+use crate::cache::Cache;
+use crate::diagnostics::CodeLocation;
+use crate::result::Metadata;
+use crate::signature::{Declaration, Signature};
+use crate::worker::Limits;
+
+#[global_allocator]
+static ALLOCATOR: worker::Allocator = worker::Allocator;
 
+mod cache;
+mod diagnostics;
+mod result;
+mod sandbox;
+mod signature;
 mod ty;
+mod wasm;
+mod worker;
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,70 +47,234 @@ pub struct Input {
     pub code_string: String,
     pub function_name: String,
     pub function_arguments: serde_json::Map<String, serde_json::Value>,
+    /// The ZID of the Z14/implementation, reported back in the metadata.
+    #[serde(default)]
+    pub implementation_id: Option<String>,
+    /// The Z8/function, which the signature of the implementation is checked against.
+    #[serde(default)]
+    pub function: Option<Declaration>,
+}
+
+/// How implementations are compiled and run, chosen with `WF_BACKEND`.
+#[derive(Clone, Copy)]
+enum Backend {
+    /// Compiled for the host and loaded into the evaluator with libloading.
+    Native,
+    /// Compiled to `wasm32-wasip1` and run in wasmi.
+    Wasm,
+}
+
+impl Backend {
+    fn from_env() -> Result<Backend, String> {
+        std::env::var("WF_BACKEND").map_or(Ok(Backend::Native), |name| Backend::from_name(&name))
+    }
+
+    fn from_name(name: &str) -> Result<Backend, String> {
+        match name {
+            "native" => Ok(Backend::Native),
+            "wasm" => Ok(Backend::Wasm),
+            other => Err(format!("unknown backend {other:?}, expected \"native\" or \"wasm\"")),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Backend::Native => "native",
+            Backend::Wasm => "wasm",
+        }
+    }
+
+    /// The target that implementations are compiled for, as passed to rustc.
+    fn target(self) -> Option<&'static str> {
+        match self {
+            Backend::Native => None,
+            Backend::Wasm => Some("wasm32-wasip1"),
+        }
+    }
+
+    /// Where wfrt and its dependencies were built for the target.
+    fn wfrt_dir(self) -> String {
+        match self.target() {
+            Some(target) => format!("./target/{target}/debug"),
+            None => "./target/debug".to_owned(),
+        }
+    }
+
+    /// Runs the compiled implementation at `path` in this process. WebAssembly is
+    /// sandboxed anyway, so `sandbox` only matters for the native backend.
+    fn run(self, path: &Path, args: &[u8], sandbox: bool) -> Result<Result<wfrt::Value, wfrt::Failure>, ZError> {
+        match self {
+            Backend::Native => run_native(path, args, sandbox),
+            Backend::Wasm => wasm::run(path, args),
+        }
+    }
 }
 
 // args to the function template:
 // code: the code string
 // fn_name: the name of the function
-// fn_args: `get_next(&mut values)?`, repeated by number of args
+// fn_args: `get_next(&mut values, i)?` for the index `i` of each parameter
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("--worker") {
+        return worker::main(args);
+    }
+
     let reader = BufReader::new(stdin().lock());
     let values = serde_json::Deserializer::from_reader(reader).into_iter::<Input>();
+    let rustc_version = rustc_version();
+    let cache = Cache::from_env();
+    let backend = Backend::from_env()?;
+    let limits = Limits::from_env();
+    let sandbox = sandbox::enabled();
     for input in values {
-        let input = input?;
-        let code = input.code_string;
-        let fn_name = input.function_name;
-        let fn_args = "get_next(&mut values)?,".repeat(input.function_arguments.len());
-        let compile = format!(
-            include_str!("function_template.rs"),
-            code = code,
-            fn_name = fn_name,
-            fn_args = fn_args
-        );
-        let tmp = NamedTempFile::new()?.into_temp_path();
-        let mut rustc = Command::new("rustc")
+        let mut metadata = Metadata {
+            rustc_version: rustc_version.clone(),
+            ..Metadata::default()
+        };
+        let result = evaluate(input?, backend, limits, sandbox, cache.as_ref(), &mut metadata);
+        let result = result::evaluation_result(result, metadata);
+        let mut stdout = stdout().lock();
+        serde_json::to_writer(&mut stdout, &Canonical(&result))?;
+        writeln!(stdout)?;
+    }
+
+    Ok(())
+}
+
+/// Wraps an error that is not from the evaluated function itself.
+fn internal(e: impl fmt::Display) -> ZError {
+    ZError::generic(e.to_string())
+}
+
+/// The output of `rustc --version`, such as `rustc 1.80.0 (051478957 2024-07-21)`.
+fn rustc_version() -> Option<String> {
+    let output = Command::new("rustc").arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    output.status.success().then(|| version.trim().to_owned())
+}
+
+fn evaluate(input: Input, backend: Backend, limits: Limits, sandbox: bool, cache: Option<&Cache>, metadata: &mut Metadata) -> Result<wfrt::Value, ZError> {
+    metadata.implementation_id = input.implementation_id;
+    let code = input.code_string;
+    let fn_name = input.function_name;
+    let signature = Signature::parse(&code, &fn_name)
+        .and_then(|signature| match &input.function {
+            Some(function) => signature.check(function).map(|()| signature),
+            None => Ok(signature),
+        })
+        .map_err(|e| ZError::in_evaluation(ZValue::Reference(fn_name.clone()), e))?;
+    let arguments = signature.bind(input.function_arguments)?;
+    let fn_args: String = (0..signature.params.len()).map(|i| format!("get_next(&mut values, {i})?,")).collect();
+    let compile = format!(
+        include_str!("function_template.rs"),
+        code = code,
+        fn_name = fn_name,
+        fn_args = fn_args
+    );
+    let code_location = CodeLocation::find(&compile, &code);
+    let wfrt_dir = backend.wfrt_dir();
+    let wfrt = format!("{wfrt_dir}/libwfrt.rlib");
+    // without the version of rustc, an entry could have been compiled by another one.
+    let cache_key = cache.zip(metadata.rustc_version.as_deref()).and_then(|(_, v)| {
+        Cache::key(&compile, backend.target().unwrap_or("native"), Path::new(&wfrt), v).ok()
+    });
+    // the implementation is loaded from a file of its own, which cannot be evicted.
+    let lib_path = NamedTempFile::new().map_err(internal)?.into_temp_path();
+    let cached = cache.zip(cache_key.as_deref()).is_some_and(|(cache, key)| cache.get(key, &lib_path));
+    metadata.cache_hit = cache_key.is_some().then_some(cached);
+
+    if !cached {
+        let compile_start = Instant::now();
+        let mut rustc = Command::new("rustc");
+        if let Some(target) = backend.target() {
+            // proc macros that wfrt depends on, such as serde_derive, are built for the host.
+            let host_dir = Backend::Native.wfrt_dir();
+            rustc.args(["--target", target]).arg("-L").arg(format!("dependency={host_dir}/deps"));
+        }
+        let mut rustc = rustc
             .args(["-", "-o"])
-            .arg(&tmp)
+            .arg(&lib_path)
             .args(["--crate-type", "cdylib"])
             .args(["--edition", "2021"])
-            .args(["-L", "dependency=./target/debug/deps"])
-            .args(["--extern", "wfrt=./target/debug/libwfrt.rlib"])
-            .current_dir(current_dir()?.canonicalize()?)
-            .stderr(Stdio::inherit())
+            .arg("-L")
+            .arg(format!("dependency={wfrt_dir}/deps"))
+            .arg("--extern")
+            .arg(format!("wfrt={wfrt}"))
+            .args(["--error-format", "json"])
+            .current_dir(current_dir().and_then(|d| d.canonicalize()).map_err(internal)?)
+            .stderr(Stdio::piped())
             .stdout(Stdio::piped())
             .stdin(Stdio::piped())
-            .spawn()?;
-        rustc.stdin.take().unwrap().write_all(compile.as_bytes())?;
-        if !rustc.wait()?.success() {
-            todo!("fail compilation")
+            .spawn()
+            .map_err(internal)?;
+        rustc.stdin.take().unwrap().write_all(compile.as_bytes()).map_err(internal)?;
+        let output = rustc.wait_with_output().map_err(internal)?;
+        metadata.compile_duration = Some(compile_start.elapsed());
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = diagnostics::compile_error(&stderr, code_location.as_ref());
+            return Err(ZError::in_evaluation(ZValue::Reference(fn_name), error));
+        }
+        // failing to cache the implementation only means compiling it again next time.
+        if let Some((cache, key)) = cache.zip(cache_key.as_deref()) {
+            let _ = cache.insert(key, &lib_path);
         }
-        
-        let lib = unsafe {
-            Library::new(&tmp)
-        }?;
-
-        let entry = unsafe {
-            lib.get::<wfrt::ffi::Function>(b"evaluate_entrypoint\0")
-        }?;
-
-        let args = input.function_arguments.into_iter().map(|(_, v)| {
-            let ty = ty::Type::from_json(v.get("Z1K1").unwrap()).unwrap();
-            let v = ty.to_value(v).unwrap();
-            v
-        }).collect::<Vec<_>>();
-
-        let result = unsafe {
-            entry(wfrt::ffi::Bytes::from_slice(&wfrt::to_stdvec(&args)?))
+    }
+
+    let args = arguments.into_iter().map(|(_, v)| {
+        let Some(ty) = ty::Type::of_value(&v) else {
+            return Err(ZError::not_implemented(format!("arguments of type {}", v["Z1K1"])));
         };
-        let result: Result<wfrt::Value, String> = wfrt::from_bytes(&result.into_vec())?;
-        let val = result?;
-        dbg!(val);
+        ty.to_value(v)
+    }).collect::<Result<Vec<_>, _>>()?;
 
-        // TODO: serialize the value
+    let args = wfrt::to_stdvec(&args).map_err(internal)?;
+    let execution_start = Instant::now();
+    let result = worker::run(backend, &lib_path, &args, limits, sandbox, &mut metadata.peak_memory);
+    metadata.execution_duration = Some(execution_start.elapsed());
+    let result = result.map_err(|e| match e {
+        worker::WorkerError::Internal(e) => e,
+        e => ZError::in_evaluation(ZValue::Reference(fn_name.clone()), e.into()),
+    })?;
 
-        lib.close()?;
-    }
+    result.map_err(|failure| {
+        let error = match failure {
+            wfrt::Failure::Arguments(e) => ZError::generic(e),
+            wfrt::Failure::OutOfRange { index, value } | wfrt::Failure::DuplicateKey { index, key: value } => {
+                ZError::new(ErrorType::ArgumentValueError)
+                    .with_arg(1, signature.params[index].name.clone())
+                    .with_arg(2, result::to_zvalue(value))
+            }
+            wfrt::Failure::Panic(panic) => diagnostics::panic_error(&panic, code_location.as_ref()),
+        };
+        ZError::in_evaluation(ZValue::Reference(fn_name), error)
+    })
+}
 
-    Ok(())
+/// Loads the cdylib at `path` into the evaluator and calls its entry point. If
+/// asked to, the sandbox is entered first, so that loading it is sandboxed too.
+fn run_native(path: &Path, args: &[u8], sandbox: bool) -> Result<Result<wfrt::Value, wfrt::Failure>, ZError> {
+    let path = if sandbox {
+        sandbox::enter(path).map_err(|e| internal(format_args!("could not enter the sandbox: {e}")))?
+    } else {
+        path.to_owned()
+    };
+
+    let lib = unsafe {
+        Library::new(path)
+    }.map_err(internal)?;
+
+    let entry = unsafe {
+        lib.get::<wfrt::ffi::Function>(b"evaluate_entrypoint\0")
+    }.map_err(internal)?;
+
+    let result = unsafe {
+        entry(wfrt::ffi::Bytes::from_slice(args))
+    };
+    let result = wfrt::from_bytes(&result.into_vec()).map_err(internal)?;
+
+    lib.close().map_err(internal)?;
+    Ok(result)
 }
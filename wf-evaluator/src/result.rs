@@ -0,0 +1,126 @@
+//! Turning the outcome of an evaluation into a Z22/evaluation result.
+
+use std::time::Duration;
+
+use wfrt::num_bigint::Sign as BigSign;
+use wikifunctions::model::{
+    Boolean, Float64, Integer, NaturalNumber, Object, Pair, Sign, TypedMap, Void, ZError, ZString, ZUnit, ZValue,
+};
+
+pub type EvaluationResult = Object<Pair<ZValue, TypedMap<ZString, ZValue>>>;
+
+/// What is known about an evaluation besides its result. This becomes the Z883/map
+/// in Z22K2, with every value but the implementation written as a Z6/string.
+#[derive(Default)]
+pub struct Metadata {
+    pub implementation_id: Option<String>,
+    pub rustc_version: Option<String>,
+    pub compile_duration: Option<Duration>,
+    pub execution_duration: Option<Duration>,
+    /// The peak resident memory of the worker that ran the implementation, in bytes.
+    pub peak_memory: Option<u64>,
+    /// Whether the compiled implementation was reused rather than compiled.
+    pub cache_hit: Option<bool>,
+}
+
+fn memory(bytes: u64) -> String {
+    format!("{:.2} MiB", bytes as f64 / 1048576.0)
+}
+
+fn duration(d: Duration) -> String {
+    format!("{:.3} ms", d.as_secs_f64() * 1000.0)
+}
+
+impl Metadata {
+    fn into_map(self, error: Option<ZError>) -> TypedMap<ZString, ZValue> {
+        let cache = self.cache_hit.map(|hit| if hit { "hit" } else { "miss" }.to_owned());
+        let strings = [
+            ("programmingLanguageVersion", self.rustc_version),
+            ("compilationDuration", self.compile_duration.map(duration)),
+            ("compilationCache", cache),
+            ("executionDuration", self.execution_duration.map(duration)),
+            ("peakMemoryUsage", self.peak_memory.map(memory)),
+        ];
+        let implementation = self.implementation_id.map(ZValue::Reference);
+        std::iter::once(("implementationId", implementation))
+            .chain(strings.map(|(key, value)| (key, value.map(ZValue::String))))
+            .chain([("errors", error.map(ZValue::from))])
+            .filter_map(|(key, value)| Some((key.into(), value?)))
+            .collect()
+    }
+}
+
+/// The ZObject that stands for a type of returned values.
+fn type_zvalue(ty: wfrt::ValueType) -> ZValue {
+    match ty {
+        wfrt::ValueType::Unit => ZValue::Reference("Z21".to_owned()),
+        wfrt::ValueType::String => ZValue::Reference("Z6".to_owned()),
+        wfrt::ValueType::Boolean => ZValue::Reference("Z40".to_owned()),
+        wfrt::ValueType::List(elem) => ZValue::list_type(type_zvalue(*elem)),
+        wfrt::ValueType::Pair(first, second) => ZValue::pair_type(type_zvalue(*first), type_zvalue(*second)),
+        wfrt::ValueType::Map(key, value) => ZValue::map_type(type_zvalue(*key), type_zvalue(*value)),
+        wfrt::ValueType::Natural => ZValue::Reference("Z13518".to_owned()),
+        wfrt::ValueType::Integer => ZValue::Reference("Z16683".to_owned()),
+        wfrt::ValueType::Float => ZValue::Reference("Z20838".to_owned()),
+    }
+}
+
+fn natural_number(n: &impl ToString) -> NaturalNumber {
+    NaturalNumber {
+        value: n.to_string().as_str().into(),
+    }
+}
+
+/// Converts a value returned by the function back into a ZObject.
+pub fn to_zvalue(value: wfrt::Value) -> ZValue {
+    match value {
+        wfrt::Value::Unit => ZValue::from_object(&Object::new(ZUnit {})).expect("units are always valid objects"),
+        wfrt::Value::String(s) => ZValue::String(s),
+        wfrt::Value::Boolean(b) => ZValue::from_object(&Object::new(Boolean::from(b)))
+            .expect("booleans are always valid objects"),
+        wfrt::Value::List(elem, items) => ZValue::list(type_zvalue(elem), items.into_iter().map(to_zvalue)),
+        wfrt::Value::Pair(first, second) => ZValue::pair(
+            type_zvalue(first.value_type()),
+            type_zvalue(second.value_type()),
+            to_zvalue(*first),
+            to_zvalue(*second),
+        ),
+        wfrt::Value::Map(key, value, entries) => ZValue::map(
+            type_zvalue(key),
+            type_zvalue(value),
+            entries.into_iter().map(|(k, v)| (to_zvalue(k), to_zvalue(v))),
+        ),
+        wfrt::Value::Natural(n) => ZValue::from_object(&Object::new(natural_number(&n)))
+            .expect("numbers are always valid objects"),
+        wfrt::Value::Integer(n) => {
+            let sign = match n.sign() {
+                BigSign::Minus => Sign::Negative,
+                BigSign::NoSign => Sign::Neutral,
+                BigSign::Plus => Sign::Positive,
+            };
+            let integer = Integer {
+                sign,
+                absolute_value: natural_number(n.magnitude()),
+            };
+            ZValue::from_object(&Object::new(integer)).expect("numbers are always valid objects")
+        }
+        wfrt::Value::Float(f) => ZValue::from_object(&Object::new(Float64::from(f)))
+            .expect("numbers are always valid objects"),
+    }
+}
+
+/// Builds the Z22 for an evaluation. A failed evaluation returns Z24/void, with
+/// the error under "errors" in the metadata.
+pub fn evaluation_result(result: Result<wfrt::Value, ZError>, metadata: Metadata) -> EvaluationResult {
+    let (value, metadata) = match result {
+        Ok(value) => (to_zvalue(value), metadata.into_map(None)),
+        Err(e) => {
+            let void = ZValue::from_object(&Object::new(Void::default())).expect("void is a valid object");
+            (void, metadata.into_map(Some(e)))
+        }
+    };
+    Object::new(Pair {
+        left: value,
+        right: metadata,
+    })
+}
@@ -0,0 +1,188 @@
+//! An opt-in sandbox for the native backend, enabled with `WF_SANDBOX=on`.
+//!
+//! Before the compiled implementation is loaded, the worker moves into new user,
+//! mount and network namespaces and changes its root to a read-only tmpfs that
+//! holds nothing but a copy of the implementation, so that there are no other
+//! files to read or write. It then installs a seccomp filter that only allows the
+//! system calls that computing, allocating and starting threads need. Signals
+//! can only be sent to the worker itself. Any other system call kills the worker
+//! with `SIGSYS`, which is reported as a sandbox violation.
+//!
+//! The implementation is only loaded once all of this is in place, so that its
+//! static constructors are sandboxed too.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use seccompiler::{
+    BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter, SeccompRule,
+};
+
+/// The name of the copy of the implementation, in the root of the sandbox.
+const IMPLEMENTATION: &str = "implementation.so";
+
+pub fn enabled() -> bool {
+    env::var("WF_SANDBOX").is_ok_and(|v| v == "on")
+}
+
+/// Moves this process into the sandbox with a copy of the implementation at
+/// `library`, returning the path of the copy inside it. The process must still
+/// be single-threaded.
+pub fn enter(library: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let contents = fs::read(library)?;
+    // the new root is mounted over the directory of the implementation, which is
+    // known to exist and is hidden from the sandbox anyway.
+    let root = library.parent().ok_or("the implementation has no directory")?;
+    enter_namespaces(root, &contents)?;
+    let arch = env::consts::ARCH.try_into()?;
+    // threads are started with `clone3` first, whose flags cannot be checked by a
+    // filter. It fails with `ENOSYS`, so that libc falls back to `clone`.
+    let clone3 = SeccompFilter::new(
+        [(libc::SYS_clone3, vec![])].into_iter().collect(),
+        SeccompAction::Allow,
+        SeccompAction::Errno(libc::ENOSYS as u32),
+        arch,
+    )?;
+    let allowed = SeccompFilter::new(allowed_syscalls()?, SeccompAction::Trap, SeccompAction::Allow, arch)?;
+    for filter in [clone3, allowed] {
+        seccompiler::apply_filter(&BpfProgram::try_from(filter)?)?;
+    }
+    Ok(Path::new("/").join(IMPLEMENTATION))
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn enter_namespaces(root: &Path, implementation: &[u8]) -> io::Result<()> {
+    // SAFETY: `getuid` and `getgid` cannot fail.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let c_root = c_path(root)?;
+    // SAFETY: `unshare` takes no pointers.
+    check(unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET) })?;
+    // files can only be created once the user and group are mapped into the namespace.
+    fs::write("/proc/self/setgroups", "deny")?;
+    fs::write("/proc/self/uid_map", format!("0 {uid} 1"))?;
+    fs::write("/proc/self/gid_map", format!("0 {gid} 1"))?;
+    // SAFETY: these are called with valid, nul-terminated paths.
+    unsafe {
+        check(libc::mount(ptr::null(), c"/".as_ptr(), ptr::null(), libc::MS_REC | libc::MS_PRIVATE, ptr::null()))?;
+        let flags = libc::MS_NOSUID | libc::MS_NODEV;
+        check(libc::mount(c"tmpfs".as_ptr(), c_root.as_ptr(), c"tmpfs".as_ptr(), flags, ptr::null()))?;
+        fs::write(root.join(IMPLEMENTATION), implementation)?;
+        check(libc::mount(ptr::null(), c_root.as_ptr(), ptr::null(), flags | libc::MS_REMOUNT | libc::MS_RDONLY, ptr::null()))?;
+        check(libc::chroot(c_root.as_ptr()))?;
+        check(libc::chdir(c"/".as_ptr()))
+    }
+}
+
+/// A rule matching when argument `arg`, an `int`, is `value`.
+fn arg_is(arg: u8, value: libc::c_int) -> Result<SeccompRule, seccompiler::BackendError> {
+    let condition = SeccompCondition::new(arg, SeccompCmpArgLen::Dword, SeccompCmpOp::Eq, value as u32 as u64)?;
+    SeccompRule::new(vec![condition])
+}
+
+/// A rule matching when the flags in argument `arg` include none of `flags`.
+fn no_flags(arg: u8, flags: libc::c_int) -> Result<SeccompRule, seccompiler::BackendError> {
+    let condition = SeccompCondition::new(arg, SeccompCmpArgLen::Dword, SeccompCmpOp::MaskedEq(flags as u64), 0)?;
+    SeccompRule::new(vec![condition])
+}
+
+/// The system calls that the filter allows, with the conditions for when they are allowed.
+fn allowed_syscalls() -> Result<BTreeMap<i64, Vec<SeccompRule>>, seccompiler::BackendError> {
+    let always = [
+        // memory
+        libc::SYS_brk,
+        libc::SYS_mmap,
+        libc::SYS_munmap,
+        libc::SYS_mremap,
+        libc::SYS_mprotect,
+        libc::SYS_madvise,
+        // threads, which `clone` below is limited to
+        libc::SYS_clone3,
+        libc::SYS_futex,
+        libc::SYS_set_robust_list,
+        libc::SYS_rseq,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_gettid,
+        libc::SYS_getpid,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        // signals, as long as they are not sent anywhere
+        libc::SYS_rt_sigaction,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        libc::SYS_restart_syscall,
+        // time and randomness
+        libc::SYS_clock_gettime,
+        libc::SYS_clock_getres,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_nanosleep,
+        libc::SYS_gettimeofday,
+        libc::SYS_getrandom,
+        // file descriptors that are already open, and reading the root of the
+        // sandbox, which only has the implementation in it
+        libc::SYS_read,
+        libc::SYS_readv,
+        libc::SYS_pread64,
+        libc::SYS_write,
+        libc::SYS_writev,
+        libc::SYS_close,
+        libc::SYS_lseek,
+        libc::SYS_fcntl,
+        libc::SYS_fstat,
+        libc::SYS_newfstatat,
+        libc::SYS_statx,
+        libc::SYS_readlinkat,
+        libc::SYS_faccessat,
+        libc::SYS_faccessat2,
+        libc::SYS_getdents64,
+        libc::SYS_getcwd,
+    ];
+    #[cfg(target_arch = "x86_64")]
+    let always = always.into_iter().chain([
+        libc::SYS_stat,
+        libc::SYS_lstat,
+        libc::SYS_access,
+        libc::SYS_readlink,
+        libc::SYS_time,
+    ]);
+    let mut rules: BTreeMap<_, _> = always.into_iter().map(|syscall| (syscall, vec![])).collect();
+
+    const WRITE_FLAGS: libc::c_int = libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC;
+    rules.insert(libc::SYS_openat, vec![no_flags(2, WRITE_FLAGS)?]);
+    #[cfg(target_arch = "x86_64")]
+    rules.insert(libc::SYS_open, vec![no_flags(1, WRITE_FLAGS)?]);
+    // `clone` without `CLONE_THREAD` starts a process rather than a thread.
+    let thread = libc::CLONE_THREAD as u64;
+    let is_thread = SeccompCondition::new(0, SeccompCmpArgLen::Qword, SeccompCmpOp::MaskedEq(thread), thread)?;
+    rules.insert(libc::SYS_clone, vec![SeccompRule::new(vec![is_thread])?]);
+    // signals can only be sent to this process, as in `abort` and `raise`.
+    // SAFETY: `getpid` cannot fail.
+    let pid = unsafe { libc::getpid() };
+    for syscall in [libc::SYS_kill, libc::SYS_tgkill, libc::SYS_rt_sigqueueinfo, libc::SYS_rt_tgsigqueueinfo] {
+        rules.insert(syscall, vec![arg_is(0, pid)?]);
+    }
+    // resource limits can only be read or changed for this process, which libc
+    // does when it starts a thread.
+    rules.insert(libc::SYS_prlimit64, vec![arg_is(0, 0)?, arg_is(0, pid)?]);
+    // threads are named by the standard library.
+    rules.insert(libc::SYS_prctl, vec![arg_is(0, libc::PR_SET_NAME)?, arg_is(0, libc::PR_GET_NAME)?]);
+    Ok(rules)
+}
@@ -0,0 +1,225 @@
+//! Reading the signature of the evaluated function from the code string, so that
+//! arguments are passed to the parameters with the same names as their keys, and
+//! so that it can be checked against the declaration of the function before it
+//! is compiled.
+
+use std::collections::BTreeSet;
+
+use quote::ToTokens;
+use syn::{FnArg, GenericArgument, Item, Pat, PathArguments, ReturnType};
+use wikifunctions::model::{Function, Object, ZError, ZValue};
+
+use crate::ty::Type;
+
+/// A Z8/function as given in the input, of which only the types of the arguments
+/// and the return type are used.
+pub type Declaration = Object<Function<ZValue, ZValue, ZValue, ZValue, ZValue>>;
+
+/// The signature of the evaluated function.
+pub struct Signature {
+    /// The parameters, in order.
+    pub params: Vec<Param>,
+    pub output: syn::Type,
+}
+
+pub struct Param {
+    pub name: String,
+    pub ty: syn::Type,
+}
+
+fn error_at(span: proc_macro2::Span, message: impl std::fmt::Display) -> ZError {
+    let start = span.start();
+    ZError::generic(format!("line {}, column {}: {message}", start.line, start.column + 1))
+}
+
+fn syntax_error(e: syn::Error) -> ZError {
+    error_at(e.span(), e)
+}
+
+impl Signature {
+    /// Finds the function `fn_name` in `code`.
+    pub fn parse(code: &str, fn_name: &str) -> Result<Signature, ZError> {
+        let tokens: proc_macro2::TokenStream = code
+            .parse()
+            .map_err(|e: proc_macro2::LexError| error_at(e.span(), "unbalanced delimiters or an invalid token"))?;
+        let file: syn::File = syn::parse2(tokens).map_err(syntax_error)?;
+        let function = file
+            .items
+            .iter()
+            .find_map(|item| match item {
+                Item::Fn(f) if f.sig.ident == fn_name => Some(f),
+                _ => None,
+            })
+            .ok_or_else(|| ZError::generic(format!("the code does not define a function named {fn_name}")))?;
+        let params = function
+            .sig
+            .inputs
+            .iter()
+            .map(|input| match input {
+                FnArg::Typed(param) => match &*param.pat {
+                    Pat::Ident(pat) => Ok(Param {
+                        name: pat.ident.to_string(),
+                        ty: (*param.ty).clone(),
+                    }),
+                    _ => Err(syntax_error(syn::Error::new_spanned(
+                        &param.pat,
+                        "parameters must be named after the keys of the arguments",
+                    ))),
+                },
+                FnArg::Receiver(receiver) => Err(syntax_error(syn::Error::new_spanned(
+                    receiver,
+                    "the function cannot take `self`",
+                ))),
+            })
+            .collect::<Result<_, _>>()?;
+        let output = match &function.sig.output {
+            ReturnType::Default => syn::parse_quote!(()),
+            ReturnType::Type(_, ty) => (**ty).clone(),
+        };
+        Ok(Signature { params, output })
+    }
+
+    /// Orders `arguments` by the parameters that they are passed to. Every
+    /// parameter needs an argument with its name as the key, and every argument
+    /// needs a parameter.
+    pub fn bind(
+        &self,
+        mut arguments: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Vec<(String, serde_json::Value)>, ZError> {
+        let params: BTreeSet<_> = self.params.iter().map(|param| &param.name).collect();
+        let mut errors: Vec<_> = arguments
+            .keys()
+            .filter(|key| !params.contains(key))
+            .map(|key| ZError::generic(format!("unexpected argument {key}: the function has no parameter with that name")))
+            .collect();
+        let missing: Vec<_> = self.params.iter().filter(|param| !arguments.contains_key(&param.name)).collect();
+        if !missing.is_empty() {
+            let keys = arguments.keys().map(|key| ZValue::String(key.clone()));
+            let keys = ZValue::list(ZValue::Reference("Z6".to_owned()), keys);
+            errors.extend(missing.into_iter().map(|param| ZError::key_not_found(&param.name, keys.clone())));
+        }
+        match errors.len() {
+            0 => Ok(self.params.iter().map(|param| (param.name.clone(), arguments.remove(&param.name).unwrap())).collect()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ZError::list(errors)),
+        }
+    }
+
+    /// Compares the signature with the declared arguments and return type of the
+    /// function. Types that the evaluator does not know are left to rustc.
+    pub fn check(&self, function: &Declaration) -> Result<(), ZError> {
+        let arguments = &function.value.arguments.inner;
+        let mut errors = Vec::new();
+        for argument in arguments {
+            let key = &argument.key.value;
+            let ty = Type::from_zvalue(&argument.ty);
+            match (self.params.iter().find(|param| param.name == *key), ty) {
+                (None, ty) => {
+                    let expected = ty.map_or_else(|| format!("{key}: ..."), |ty| format!("{key}: {}", ty.rust_type()));
+                    errors.push(ZError::generic(format!(
+                        "the function has no parameter for the argument {key}, expected `{expected}`"
+                    )));
+                }
+                (Some(param), Some(ty)) if mismatches(&ty, &param.ty) => {
+                    let actual = display(&param.ty);
+                    let message = format!(
+                        "the argument {key} is a {ty}, which is `{}` in Rust, but the parameter is `{key}: {actual}`",
+                        ty.rust_type()
+                    );
+                    errors.push(
+                        ZError::argument_type_mismatch(argument.ty.clone(), actual, key.clone())
+                            .with_arg(4, ZError::generic(message)),
+                    );
+                }
+                _ => {}
+            }
+        }
+        for param in &self.params {
+            if !arguments.iter().any(|argument| argument.key.value == param.name) {
+                errors.push(ZError::generic(format!(
+                    "the parameter {} is not an argument of the function",
+                    param.name
+                )));
+            }
+        }
+        // only when the keys do not tell what is missing, such as when a key appears twice.
+        if arguments.len() != self.params.len() && errors.is_empty() {
+            errors.push(ZError::argument_count_mismatch(arguments.len(), self.params.len()));
+        }
+        let return_type = &function.value.return_type;
+        if let Some(ty) = Type::from_zvalue(return_type) {
+            if mismatches(&ty, &self.output) {
+                let actual = display(&self.output);
+                let message = format!(
+                    "the function returns a {ty}, which is `{}` in Rust, but the implementation returns `{actual}`",
+                    ty.rust_type()
+                );
+                errors.push(ZError::return_type_mismatch(return_type.clone(), actual).with_arg(4, ZError::generic(message)));
+            }
+        }
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ZError::list(errors)),
+        }
+    }
+}
+
+/// Whether `rust` is clearly not how values of `ty` are written in Rust. Paths are
+/// compared by their last segment, so that `std::string::String` is a `String`.
+/// Anything else, such as a type alias, might still be the right type, and is left
+/// to rustc.
+fn mismatches(ty: &Type, rust: &syn::Type) -> bool {
+    match rust {
+        syn::Type::Paren(paren) => mismatches(ty, &paren.elem),
+        syn::Type::Group(group) => mismatches(ty, &group.elem),
+        syn::Type::Tuple(tuple) => match (ty, &tuple.elems.iter().collect::<Vec<_>>()[..]) {
+            (Type::Unit, []) => false,
+            (Type::Pair(first, second), [a, b]) => mismatches(first, a) || mismatches(second, b),
+            _ => true,
+        },
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            // lifetimes and consts are not types of values.
+            let args: Vec<_> = match &last.arguments {
+                PathArguments::None => Vec::new(),
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                PathArguments::Parenthesized(_) => return false,
+            };
+            match (ty, last.ident.to_string().as_str(), &args[..]) {
+                (Type::String, "String", []) | (Type::Boolean, "bool", []) => false,
+                (Type::Natural, "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "BigUint", []) => false,
+                (Type::Integer, "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "BigInt", []) => false,
+                (Type::Float, "f64", []) => false,
+                // the allocator of a `Vec` and the hasher of a `HashMap` can be anything.
+                (Type::List(elem), "Vec", [rust, ..]) => mismatches(elem, rust),
+                (Type::Map(key, value), "BTreeMap" | "HashMap", [k, v, ..]) => mismatches(key, k) || mismatches(value, v),
+                (
+                    _,
+                    "String" | "bool" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "BigUint" | "i8" | "i16" | "i32"
+                    | "i64" | "i128" | "isize" | "BigInt" | "f32" | "f64" | "Vec" | "BTreeMap" | "HashMap",
+                    _,
+                ) => true,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// A Rust type as it would be written, rather than as tokens separated by spaces.
+fn display(ty: &syn::Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    [(" < ", "<"), ("< ", "<"), (" >", ">"), (" ,", ","), (" :: ", "::"), (":: ", "::"), ("& ", "&"), ("( ", "("), (" )", ")")]
+        .into_iter()
+        .fold(tokens, |s, (from, to)| s.replace(from, to))
+}
@@ -1,53 +1,280 @@
-use serde_json::{json, Value as JsonValue};
+use std::fmt;
 
-#[derive(Clone, Copy)]
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use wfrt::num_bigint::{BigInt, BigUint};
+use wikifunctions::model::error::ErrorType;
+use wikifunctions::model::{Boolean, Float64, Integer, NaturalNumber, Object, Sign, ZError, ZUnit, ZValue};
+
+#[derive(Clone, PartialEq, Eq)]
 pub enum Type {
     String,
     /// Z21/unit
     Unit,
+    /// Z40/boolean
     Boolean,
-    List,
-    Pair,
-    Map,
+    /// Z881/typed list, with the type of its elements
+    List(Box<Type>),
+    /// Z882/typed pair, with the types of its first and second element
+    Pair(Box<Type>, Box<Type>),
+    /// Z883/typed map, with the types of its keys and values
+    Map(Box<Type>, Box<Type>),
+    /// Z13518/natural number
+    Natural,
+    /// Z16683/integer
+    Integer,
+    /// Z20838/float64
+    Float,
+}
+
+/// The ZID that `v` refers to, written either as a string or as a Z9/reference.
+fn reference(v: &JsonValue) -> Option<&str> {
+    match v {
+        JsonValue::String(s) => Some(s),
+        JsonValue::Object(obj) if obj.get("Z1K1")?.as_str()? == "Z9" => obj.get("Z9K1")?.as_str(),
+        _ => None,
+    }
 }
 
 impl Type {
-    pub fn from_json(v: &JsonValue) -> Option<Self> {
+    /// Finds the type of an argument. Booleans are usually passed as a
+    /// reference to Z41/Z42, and units as a reference to Z24/void, so their `Z1K1`
+    /// alone is not enough. Lists in canonical form are arrays that start with the
+    /// type of their elements.
+    pub fn of_value(v: &JsonValue) -> Option<Self> {
         match v {
-            JsonValue::String(s) if s == "Z6" => Some(Type::String),
-            JsonValue::Object(obj) => match obj.get("Z1K1")? {
-                JsonValue::String(ty) if ty == "Z9" => {
-                    let Some(JsonValue::String(ref_id)) = obj.get("Z9K1") else {
-                        return None;
-                    };
-
-                    (ref_id == "Z21").then(|| Type::Unit)
-                }
-                JsonValue::Object(obj) => {
-                    // result of a Z7/function call
-                    if obj.get("Z1K1")? != &json!({"Z1K1": "Z9", "Z9K1": "Z7"}) {
-                        return None;
-                    }
+            JsonValue::Array(items) => Some(Type::List(Box::new(Type::from_json(items.first()?)?))),
+            _ => match v.get("Z1K1")? {
+                JsonValue::String(s) if s == "Z9" => match v.get("Z9K1")?.as_str()? {
+                    "Z41" | "Z42" => Some(Type::Boolean),
+                    "Z24" => Some(Type::Unit),
+                    _ => None,
+                },
+                ty => Type::from_json(ty),
+            },
+        }
+    }
 
-                    match obj.get("Z7K1")?.get("Z9K1")?.as_str()? {
-                        "Z881" => Some(Type::List),
-                        "Z882" => Some(Type::Pair),
-                        _ => None,
-                    }
+    fn from_reference(id: &str) -> Option<Self> {
+        match id {
+            "Z6" => Some(Type::String),
+            "Z21" => Some(Type::Unit),
+            "Z40" => Some(Type::Boolean),
+            "Z13518" => Some(Type::Natural),
+            "Z16683" => Some(Type::Integer),
+            "Z20838" => Some(Type::Float),
+            _ => None,
+        }
+    }
+
+    /// The type declared by a ZObject, such as the type of a Z17/argument.
+    pub fn from_zvalue(v: &ZValue) -> Option<Self> {
+        match v {
+            ZValue::Reference(id) => Type::from_reference(id),
+            call if call.ty()?.as_reference() == Some("Z7") => {
+                match call.get("Z7K1")?.as_reference()? {
+                    "Z881" => Some(Type::List(Box::new(Type::from_zvalue(call.get("Z881K1")?)?))),
+                    "Z882" => Some(Type::Pair(
+                        Box::new(Type::from_zvalue(call.get("Z882K1")?)?),
+                        Box::new(Type::from_zvalue(call.get("Z882K2")?)?),
+                    )),
+                    "Z883" => Some(Type::Map(
+                        Box::new(Type::from_zvalue(call.get("Z883K1")?)?),
+                        Box::new(Type::from_zvalue(call.get("Z883K2")?)?),
+                    )),
+                    _ => None,
                 }
+            }
+            _ => None,
+        }
+    }
+
+    /// How values of this type are written in Rust.
+    pub fn rust_type(&self) -> String {
+        match self {
+            Type::String => "String".to_owned(),
+            Type::Unit => "()".to_owned(),
+            Type::Boolean => "bool".to_owned(),
+            Type::List(elem) => format!("Vec<{}>", elem.rust_type()),
+            Type::Pair(first, second) => format!("({}, {})", first.rust_type(), second.rust_type()),
+            Type::Map(key, value) => format!("BTreeMap<{}, {}>", key.rust_type(), value.rust_type()),
+            Type::Natural => "u64".to_owned(),
+            Type::Integer => "i64".to_owned(),
+            Type::Float => "f64".to_owned(),
+        }
+    }
+
+    /// Reads a type written in JSON, in either canonical or normal form.
+    pub fn from_json(v: &JsonValue) -> Option<Self> {
+        match v {
+            JsonValue::String(id) => Type::from_reference(id),
+            JsonValue::Object(obj) => match reference(obj.get("Z1K1")?)? {
+                "Z9" => Type::from_reference(obj.get("Z9K1")?.as_str()?),
+                // result of a Z7/function call
+                "Z7" => match reference(obj.get("Z7K1")?)? {
+                    "Z881" => Some(Type::List(Box::new(Type::from_json(obj.get("Z881K1")?)?))),
+                    "Z882" => Some(Type::Pair(
+                        Box::new(Type::from_json(obj.get("Z882K1")?)?),
+                        Box::new(Type::from_json(obj.get("Z882K2")?)?),
+                    )),
+                    "Z883" => Some(Type::Map(
+                        Box::new(Type::from_json(obj.get("Z883K1")?)?),
+                        Box::new(Type::from_json(obj.get("Z883K2")?)?),
+                    )),
+                    _ => None,
+                },
                 _ => None,
             },
             _ => None,
         }
     }
-    
-    pub fn to_value(self, mut v: JsonValue) -> Option<wfrt::Value> {
+
+    /// The type that values of this type have once converted.
+    pub fn value_type(&self) -> wfrt::ValueType {
         match self {
-            Type::String => match v.get_mut("Z6K1")?.take() {
-                JsonValue::String(s) => Some(wfrt::Value::String(s)),
-                _ => None
+            Type::Unit => wfrt::ValueType::Unit,
+            Type::String => wfrt::ValueType::String,
+            Type::Boolean => wfrt::ValueType::Boolean,
+            Type::List(elem) => wfrt::ValueType::List(Box::new(elem.value_type())),
+            Type::Pair(first, second) => wfrt::ValueType::Pair(Box::new(first.value_type()), Box::new(second.value_type())),
+            Type::Map(key, value) => wfrt::ValueType::Map(Box::new(key.value_type()), Box::new(value.value_type())),
+            Type::Natural => wfrt::ValueType::Natural,
+            Type::Integer => wfrt::ValueType::Integer,
+            Type::Float => wfrt::ValueType::Float,
+        }
+    }
+
+    /// A Z502/not well-formed error for `v`, which is not a valid value of this type.
+    fn not_well_formed(&self, v: &JsonValue) -> ZError {
+        let value = ZValue::deserialize(v).unwrap_or_else(|_| ZValue::String(v.to_string()));
+        ZError::new(ErrorType::NotWellFormed).with_arg(1, self.to_string()).with_arg(2, value)
+    }
+
+    /// Converts an argument of this type. Numbers are checked by the model, and
+    /// keep the Z502/not well-formed or Z516/argument value error that it reports.
+    pub fn to_value(&self, mut v: JsonValue) -> Result<wfrt::Value, ZError> {
+        match self {
+            Type::Unit if reference(&v) == Some("Z24") => Ok(wfrt::Value::Unit),
+            Type::Unit => {
+                Object::<ZUnit>::deserialize(&v).map_err(|_| self.not_well_formed(&v))?;
+                Ok(wfrt::Value::Unit)
+            }
+            Type::String => match v {
+                // strings in canonical form, such as the elements of a list
+                JsonValue::String(s) => Ok(wfrt::Value::String(s)),
+                _ => match v.get_mut("Z6K1") {
+                    Some(JsonValue::String(s)) => Ok(wfrt::Value::String(std::mem::take(s))),
+                    _ => Err(self.not_well_formed(&v)),
+                },
             },
-            _ => None,
+            Type::Boolean => {
+                let b = Object::<Boolean>::deserialize(&v).map_err(|_| self.not_well_formed(&v))?;
+                Ok(wfrt::Value::Boolean(b.value.into()))
+            }
+            Type::List(elem) => {
+                let items = list_items(elem, v)?.into_iter().map(|item| elem.to_value(item)).collect::<Result<_, _>>()?;
+                Ok(wfrt::Value::List(elem.value_type(), items))
+            }
+            Type::Pair(first_type, second_type) => {
+                let (first, second) = take_pair(&mut v).ok_or_else(|| self.not_well_formed(&v))?;
+                Ok(wfrt::Value::Pair(Box::new(first_type.to_value(first)?), Box::new(second_type.to_value(second)?)))
+            }
+            // a list of pairs of keys and values
+            Type::Map(key_type, value_type) => {
+                let pair = Type::Pair(key_type.clone(), value_type.clone());
+                let entries = match v.get_mut("K1") {
+                    Some(entries) => list_items(&pair, entries.take())?,
+                    None => return Err(self.not_well_formed(&v)),
+                };
+                let entries = entries.into_iter().map(|mut entry| {
+                    let (key, value) = take_pair(&mut entry).ok_or_else(|| pair.not_well_formed(&entry))?;
+                    Ok((key_type.to_value(key)?, value_type.to_value(value)?))
+                });
+                Ok(wfrt::Value::Map(key_type.value_type(), value_type.value_type(), entries.collect::<Result<_, ZError>>()?))
+            }
+            Type::Natural => {
+                let n = Object::<NaturalNumber>::deserialize(&v).map_err(|_| self.not_well_formed(&v))?;
+                Ok(wfrt::Value::Natural(natural(&n.value)?))
+            }
+            Type::Integer => {
+                let n = Object::<Integer>::deserialize(&v).map_err(|_| self.not_well_formed(&v))?.value;
+                let value = match i64::try_from(&n) {
+                    Ok(n) => BigInt::from(n),
+                    // only when it is well-formed, but does not fit.
+                    Err(e) if e.ty() == Some(ErrorType::ArgumentValueError) => {
+                        let abs = BigInt::from(natural(&n.absolute_value)?);
+                        if n.sign == Sign::Negative { -abs } else { abs }
+                    }
+                    Err(e) => return Err(e),
+                };
+                Ok(wfrt::Value::Integer(value))
+            }
+            Type::Float => {
+                let f = Object::<Float64>::deserialize(&v).map_err(|_| self.not_well_formed(&v))?;
+                Ok(wfrt::Value::Float(f64::try_from(&f.value)?))
+            }
         }
     }
 }
+
+/// The value of `n`, checked by the model, and only parsed here if it does not fit
+/// in a `u64`.
+fn natural(n: &NaturalNumber) -> Result<BigUint, ZError> {
+    match u64::try_from(n) {
+        Ok(n) => Ok(n.into()),
+        Err(e) if e.ty() == Some(ErrorType::ArgumentValueError) => n.digits().and_then(|d| d.parse().ok()).ok_or(e),
+        Err(e) => Err(e),
+    }
+}
+
+/// Takes the first and second element of a Z882/typed pair, if it has both.
+fn take_pair(v: &mut JsonValue) -> Option<(JsonValue, JsonValue)> {
+    let obj = v.as_object_mut()?;
+    if !obj.contains_key("K1") || !obj.contains_key("K2") {
+        return None;
+    }
+    Some((obj.remove("K1")?, obj.remove("K2")?))
+}
+
+/// The elements of a Z881/typed list of `elem`s. In canonical form, the list is an
+/// array that starts with the element type. In normal form, each list is either
+/// empty or has its first element in `K1` and the rest of the list in `K2`.
+fn list_items(elem: &Type, mut list: JsonValue) -> Result<Vec<JsonValue>, ZError> {
+    let not_well_formed = |list: &JsonValue| Type::List(Box::new(elem.clone())).not_well_formed(list);
+    if let JsonValue::Array(items) = &mut list {
+        if items.first().and_then(Type::from_json).as_ref() != Some(elem) {
+            return Err(not_well_formed(&list));
+        }
+        items.remove(0);
+        return Ok(std::mem::take(items));
+    }
+    let mut items = Vec::new();
+    loop {
+        match take_pair(&mut list) {
+            Some((head, tail)) => {
+                items.push(head);
+                list = tail;
+            }
+            None if list.as_object().is_some_and(|obj| !obj.contains_key("K1") && !obj.contains_key("K2")) => {
+                return Ok(items)
+            }
+            None => return Err(not_well_formed(&list)),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::String => "Z6/string",
+            Type::Unit => "Z21/unit",
+            Type::Boolean => "Z40/boolean",
+            Type::List(elem) => return write!(f, "Z881/typed list of {elem}"),
+            Type::Pair(first, second) => return write!(f, "Z882/typed pair of {first} and {second}"),
+            Type::Map(key, value) => return write!(f, "Z883/typed map from {key} to {value}"),
+            Type::Natural => "Z13518/natural number",
+            Type::Integer => "Z16683/integer",
+            Type::Float => "Z20838/float64",
+        })
+    }
+}
@@ -0,0 +1,210 @@
+//! Running implementations compiled to `wasm32-wasip1` in wasmi.
+//!
+//! The module only gets the parts of WASI that the standard library needs to
+//! start: writing to stdout and stderr, reading the clocks and getting random
+//! bytes. What it writes is kept in memory rather than printed. Every other WASI
+//! function fails with `ENOSYS`, so there is no way to reach the file system or
+//! the network.
+
+use std::fs;
+use std::hash::{BuildHasher, RandomState};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use wasmi::errors::{MemoryError, TableError};
+use wasmi::{Caller, Engine, Error, ExternType, Linker, Module, ResourceLimiter, Store, Val};
+use wikifunctions::model::ZError;
+
+use crate::internal;
+
+const WASI: &str = "wasi_snapshot_preview1";
+
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+const ERRNO_INVAL: i32 = 28;
+const ERRNO_NOSYS: i32 = 52;
+
+/// The WASI functions implemented here. The rest are stubs returning `ENOSYS`.
+const IMPLEMENTED: &[&str] = &[
+    "fd_write",
+    "random_get",
+    "clock_time_get",
+    "args_sizes_get",
+    "args_get",
+    "environ_sizes_get",
+    "environ_get",
+    "sched_yield",
+    "proc_exit",
+];
+
+struct Host {
+    start: Instant,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    /// Whether the memory of the module failed to grow.
+    out_of_memory: bool,
+}
+
+/// Growing the memory is limited by the address space of the worker, where wasmi
+/// allocates it, and by the maximum size of the memory, which is noted here.
+impl ResourceLimiter for Host {
+    fn memory_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> Result<bool, MemoryError> {
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: u32, _desired: u32, _maximum: Option<u32>) -> Result<bool, TableError> {
+        Ok(true)
+    }
+
+    fn memory_grow_failed(&mut self, _error: &MemoryError) {
+        self.out_of_memory = true;
+    }
+}
+
+/// The memory of the module, and the host state along with it.
+fn memory<'a>(caller: &'a mut Caller<'_, Host>) -> Option<(&'a mut [u8], &'a mut Host)> {
+    let memory = caller.get_export("memory")?.into_memory()?;
+    Some(memory.data_and_store_mut(caller))
+}
+
+fn read_u32(memory: &[u8], at: u32) -> Option<u32> {
+    let bytes = memory.get(at as usize..)?.get(..4)?;
+    Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn write(memory: &mut [u8], at: u32, bytes: &[u8]) -> Option<()> {
+    memory.get_mut(at as usize..)?.get_mut(..bytes.len())?.copy_from_slice(bytes);
+    Some(())
+}
+
+fn fd_write(mut caller: Caller<'_, Host>, fd: u32, iovs: u32, iovs_len: u32, nwritten: u32) -> i32 {
+    let Some((memory, host)) = memory(&mut caller) else { return ERRNO_FAULT };
+    let out = match fd {
+        1 => &mut host.stdout,
+        2 => &mut host.stderr,
+        _ => return ERRNO_BADF,
+    };
+    let mut written = 0u32;
+    for i in 0..iovs_len {
+        let iov = iovs.wrapping_add(i.wrapping_mul(8));
+        let (Some(ptr), Some(len)) = (read_u32(memory, iov), read_u32(memory, iov.wrapping_add(4))) else {
+            return ERRNO_FAULT;
+        };
+        let Some(bytes) = memory.get(ptr as usize..).and_then(|m| m.get(..len as usize)) else {
+            return ERRNO_FAULT;
+        };
+        out.extend_from_slice(bytes);
+        written = written.wrapping_add(len);
+    }
+    match write(memory, nwritten, &written.to_le_bytes()) {
+        Some(()) => ERRNO_SUCCESS,
+        None => ERRNO_FAULT,
+    }
+}
+
+fn random_get(mut caller: Caller<'_, Host>, buf: u32, len: u32) -> i32 {
+    let Some((memory, _)) = memory(&mut caller) else { return ERRNO_FAULT };
+    let Some(buf) = memory.get_mut(buf as usize..).and_then(|m| m.get_mut(..len as usize)) else {
+        return ERRNO_FAULT;
+    };
+    // good enough for the keys of hash maps, which is what the standard library uses it for.
+    for chunk in buf.chunks_mut(8) {
+        let random = RandomState::new().hash_one(0u8).to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+    ERRNO_SUCCESS
+}
+
+fn clock_time_get(mut caller: Caller<'_, Host>, id: u32, _precision: u64, time: u32) -> i32 {
+    let Some((memory, host)) = memory(&mut caller) else { return ERRNO_FAULT };
+    let nanos = match id {
+        0 => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos(),
+        1 => host.start.elapsed().as_nanos(),
+        _ => return ERRNO_INVAL,
+    };
+    match write(memory, time, &(nanos as u64).to_le_bytes()) {
+        Some(()) => ERRNO_SUCCESS,
+        None => ERRNO_FAULT,
+    }
+}
+
+/// Reports no arguments or environment variables.
+fn sizes_get(mut caller: Caller<'_, Host>, count: u32, size: u32) -> i32 {
+    let Some((memory, _)) = memory(&mut caller) else { return ERRNO_FAULT };
+    match write(memory, count, &[0; 4]).and_then(|()| write(memory, size, &[0; 4])) {
+        Some(()) => ERRNO_SUCCESS,
+        None => ERRNO_FAULT,
+    }
+}
+
+fn linker(engine: &Engine, module: &Module) -> Result<Linker<Host>, Error> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(WASI, "fd_write", fd_write)?;
+    linker.func_wrap(WASI, "random_get", random_get)?;
+    linker.func_wrap(WASI, "clock_time_get", clock_time_get)?;
+    linker.func_wrap(WASI, "args_sizes_get", sizes_get)?;
+    linker.func_wrap(WASI, "args_get", |_: u32, _: u32| ERRNO_SUCCESS)?;
+    linker.func_wrap(WASI, "environ_sizes_get", sizes_get)?;
+    linker.func_wrap(WASI, "environ_get", |_: u32, _: u32| ERRNO_SUCCESS)?;
+    linker.func_wrap(WASI, "sched_yield", || ERRNO_SUCCESS)?;
+    linker.func_wrap(WASI, "proc_exit", |code: i32| -> Result<(), Error> { Err(Error::i32_exit(code)) })?;
+    for import in module.imports() {
+        let ExternType::Func(ty) = import.ty() else { continue };
+        if import.module() != WASI || IMPLEMENTED.contains(&import.name()) {
+            continue;
+        }
+        linker.func_new(WASI, import.name(), ty.clone(), |_, _, results| {
+            if let Some(errno) = results.first_mut() {
+                *errno = Val::I32(ERRNO_NOSYS);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(linker)
+}
+
+/// Runs the compiled module at `path` with the postcard-encoded `args`.
+pub fn run(path: &Path, args: &[u8]) -> Result<Result<wfrt::Value, wfrt::Failure>, ZError> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, &fs::read(path).map_err(internal)?).map_err(internal)?;
+    let mut store = Store::new(&engine, Host {
+        start: Instant::now(),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        out_of_memory: false,
+    });
+    store.limiter(|host| host);
+    let instance = linker(&engine, &module)
+        .and_then(|linker| linker.instantiate(&mut store, &module)?.start(&mut store))
+        .map_err(internal)?;
+    if let Ok(initialize) = instance.get_typed_func::<(), ()>(&store, "_initialize") {
+        initialize.call(&mut store, ()).map_err(internal)?;
+    }
+    let alloc = instance.get_typed_func::<u32, u32>(&store, "wf_alloc").map_err(internal)?;
+    let evaluate = instance.get_typed_func::<(u32, u32), u64>(&store, "wf_evaluate").map_err(internal)?;
+    let memory = instance.get_memory(&store, "memory").ok_or_else(|| internal("the module has no memory"))?;
+
+    let len = u32::try_from(args.len()).map_err(internal)?;
+    let ptr = alloc.call(&mut store, len).map_err(internal)?;
+    memory.write(&mut store, ptr as usize, args).map_err(internal)?;
+    let packed = match evaluate.call(&mut store, (ptr, len)) {
+        Ok(packed) => packed,
+        // the memory of the module could not grow, so its allocator returned null.
+        Err(_) if store.data().out_of_memory => wfrt::alloc::out_of_memory(),
+        Err(e) => return trapped(e, &store.data().stderr),
+    };
+    let mut result = vec![0; packed as u32 as usize];
+    memory.read(&store, (packed >> 32) as usize, &mut result).map_err(internal)?;
+    wfrt::from_bytes(&result).map_err(internal)
+}
+
+/// Panics abort on WebAssembly, so the panic hook writes the panic to stderr as
+/// a line of JSON before the module traps.
+fn trapped(error: Error, stderr: &[u8]) -> Result<Result<wfrt::Value, wfrt::Failure>, ZError> {
+    let stderr = String::from_utf8_lossy(stderr);
+    match stderr.lines().rev().find_map(|line| serde_json::from_str::<wfrt::Panic>(line).ok()) {
+        Some(panic) => Ok(Err(wfrt::Failure::Panic(panic))),
+        None => Err(ZError::generic(format!("the implementation trapped: {error}"))),
+    }
+}
@@ -0,0 +1,332 @@
+//! Running compiled implementations in a child process, so that an infinite loop,
+//! an abort or running out of memory in the evaluated function does not take the
+//! evaluator down with it.
+//!
+//! The worker is the evaluator itself, started as `wf-evaluator --worker <backend>
+//! <path> [--sandbox]`. It reads the postcard-encoded arguments from stdin and writes the
+//! postcard-encoded result to the pipe at [`wfrt::alloc::RESULT_FD`], while its stdout
+//! and stderr, where the evaluated function's own output goes, are both the same pipe.
+//! Only the last line of that is kept. Before it starts, its CPU time and address space
+//! are limited with rlimits, and it is killed if it runs past the wall-clock limit.
+//! Which limit was exceeded is told by how the worker ended: `SIGXCPU` for the CPU
+//! time, and [`wfrt::alloc::OUT_OF_MEMORY`] in the result pipe for the address space,
+//! since the worker and the implementation both allocate with [`wfrt::Allocator`].
+//!
+//! The limits are configured with environment variables:
+//!
+//! * `WF_TIME_LIMIT`: the wall-clock limit in seconds, 10 by default.
+//! * `WF_CPU_TIME_LIMIT`: the CPU time limit in whole seconds, by default the
+//!   wall-clock limit rounded up.
+//! * `WF_MEMORY_LIMIT`: the limit on the address space of the worker in bytes,
+//!   1 GiB by default.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::FromRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wikifunctions::model::error::ErrorType;
+use wikifunctions::model::{NaturalNumber, ZError};
+
+use crate::{internal, Backend};
+
+const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(10);
+const DEFAULT_MEMORY_LIMIT: u64 = 1 << 30;
+
+/// How often the worker is checked on while it runs.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How many bytes at the end of the output of the worker are kept, for its last line.
+const OUTPUT_TAIL: usize = 4096;
+
+/// The largest encoded result that the worker can return, in bytes.
+const MAX_RESULT_SIZE: usize = 64 << 20;
+
+/// Whether this process is a worker.
+static IS_WORKER: AtomicBool = AtomicBool::new(false);
+
+/// The global allocator of the evaluator. It is [`wfrt::Allocator`] in a worker,
+/// and the system allocator otherwise, so that a failed allocation in the
+/// evaluator itself is not mistaken for a function running out of memory.
+pub struct Allocator;
+
+impl Allocator {
+    fn get() -> &'static dyn GlobalAlloc {
+        if IS_WORKER.load(Ordering::Relaxed) {
+            &wfrt::Allocator
+        } else {
+            &System
+        }
+    }
+}
+
+// SAFETY: both allocators pass everything on to `System`, so memory allocated
+// before the process became a worker can be freed after.
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Allocator::get().alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Allocator::get().alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Allocator::get().realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Limits {
+    pub time: Duration,
+    /// The CPU time limit in whole seconds, never less than one.
+    pub cpu_seconds: u64,
+    pub memory: u64,
+}
+
+impl Limits {
+    pub fn from_env() -> Limits {
+        let time = env::var("WF_TIME_LIMIT").ok().and_then(|t| Duration::try_from_secs_f64(t.parse().ok()?).ok());
+        let time = time.unwrap_or(DEFAULT_TIME_LIMIT);
+        let cpu_seconds = env::var("WF_CPU_TIME_LIMIT").ok().and_then(|c| c.parse().ok());
+        let memory = env::var("WF_MEMORY_LIMIT").ok().and_then(|m| m.parse().ok());
+        Limits {
+            time,
+            cpu_seconds: cpu_seconds.unwrap_or(time.as_secs_f64().ceil() as u64).max(1),
+            memory: memory.unwrap_or(DEFAULT_MEMORY_LIMIT),
+        }
+    }
+}
+
+/// Why the worker did not return a result.
+#[derive(Debug)]
+pub enum WorkerError {
+    /// The worker ran for longer than the wall-clock limit.
+    TimeLimit(Duration),
+    /// The worker used more CPU time than its rlimit allows.
+    CpuTimeLimit(Duration),
+    /// An allocation failed because of the limit on the address space.
+    MemoryLimit(u64),
+    /// The function made a system call that the sandbox does not allow.
+    SandboxViolation,
+    /// The worker exited without a result, such as when the function aborts.
+    Crashed(String),
+    /// The encoded result was larger than [`MAX_RESULT_SIZE`].
+    ResultTooLarge,
+    /// Something went wrong in the evaluator rather than in the function.
+    Internal(ZError),
+}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerError::TimeLimit(limit) => {
+                write!(f, "time limit exceeded: the function ran for longer than {:.3} s", limit.as_secs_f64())
+            }
+            WorkerError::CpuTimeLimit(limit) => {
+                write!(f, "time limit exceeded: the function used more than {} s of CPU time", limit.as_secs())
+            }
+            WorkerError::MemoryLimit(limit) => {
+                write!(f, "memory limit exceeded: the function used more than {:.2} MiB", *limit as f64 / 1048576.0)
+            }
+            WorkerError::SandboxViolation => f.write_str(
+                "sandbox violation: the function tried to write to the file system, start a process, \
+                 open a socket or make another system call that is not allowed",
+            ),
+            WorkerError::Crashed(reason) => write!(f, "the function crashed: {reason}"),
+            WorkerError::ResultTooLarge => {
+                write!(f, "the result is larger than {} MiB", MAX_RESULT_SIZE >> 20)
+            }
+            WorkerError::Internal(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<WorkerError> for ZError {
+    fn from(e: WorkerError) -> ZError {
+        let limit = |ty, limit: u128| ZError::new(ty).with_arg(1, NaturalNumber::from(limit as u64));
+        match e {
+            WorkerError::TimeLimit(time) => limit(ErrorType::TimeLimit, time.as_millis()),
+            WorkerError::CpuTimeLimit(time) => limit(ErrorType::CpuTimeLimit, time.as_secs().into()),
+            WorkerError::MemoryLimit(memory) => limit(ErrorType::MemoryLimit, memory.into()),
+            WorkerError::SandboxViolation => ZError::new(ErrorType::SandboxViolation),
+            WorkerError::Internal(e) => e,
+            e @ (WorkerError::Crashed(_) | WorkerError::ResultTooLarge) => ZError::generic(e.to_string()),
+        }
+    }
+}
+
+type Output = Result<Result<wfrt::Value, wfrt::Failure>, String>;
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+fn set_rlimit(resource: Resource, soft: u64, hard: u64) -> io::Result<()> {
+    let limit = libc::rlimit { rlim_cur: soft, rlim_max: hard };
+    // SAFETY: `limit` is a valid `rlimit`.
+    if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Reads the result of the worker, or `None` if it is larger than [`MAX_RESULT_SIZE`].
+/// The rest of a result that is too large is still read, so that the worker does not
+/// block on writing it.
+fn read_result(reader: impl Read + Send + 'static) -> thread::JoinHandle<Option<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let mut reader = reader.take(MAX_RESULT_SIZE as u64 + 1);
+        let _ = reader.read_to_end(&mut buf);
+        let _ = io::copy(&mut reader.into_inner(), &mut io::sink());
+        (buf.len() <= MAX_RESULT_SIZE).then_some(buf)
+    })
+}
+
+/// Reads the output of the worker, keeping only the last [`OUTPUT_TAIL`] bytes.
+fn read_tail(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut tail = VecDeque::with_capacity(OUTPUT_TAIL);
+        let mut chunk = [0; 8192];
+        while let Ok(n @ 1..) = reader.read(&mut chunk) {
+            let chunk = &chunk[n.saturating_sub(OUTPUT_TAIL)..n];
+            tail.drain(..(tail.len() + chunk.len()).saturating_sub(OUTPUT_TAIL));
+            tail.extend(chunk);
+        }
+        tail.into()
+    })
+}
+
+/// Reaps `child` with `wait4`, returning how it exited and its peak resident memory
+/// in bytes, or `None` if it is still running and `options` has `WNOHANG`.
+fn wait4(child: &Child, options: libc::c_int) -> io::Result<Option<(ExitStatus, u64)>> {
+    let mut status = 0;
+    // SAFETY: an all-zero `rusage` is valid.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `status` and `usage` are valid for writes, and `child` has not been reaped.
+    match unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, options, &mut usage) } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(None),
+        // `ru_maxrss` is in KiB.
+        _ => Ok(Some((ExitStatus::from_raw(status), usage.ru_maxrss as u64 * 1024))),
+    }
+}
+
+/// Waits for `child` until the time limit, killing it if it takes longer. Returns
+/// how it exited, whether it was killed and its peak resident memory in bytes.
+fn wait(child: &mut Child, limits: Limits) -> io::Result<(ExitStatus, bool, u64)> {
+    let start = Instant::now();
+    loop {
+        if let Some((status, peak_memory)) = wait4(child, libc::WNOHANG)? {
+            return Ok((status, false, peak_memory));
+        }
+        if start.elapsed() > limits.time {
+            child.kill()?;
+            let (status, peak_memory) = wait4(child, 0)?.expect("waited without WNOHANG");
+            return Ok((status, true, peak_memory));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Runs the compiled implementation at `path` in a worker. The peak resident memory
+/// of the worker in bytes is written to `peak_memory` once it has exited.
+pub fn run(backend: Backend, path: &Path, args: &[u8], limits: Limits, sandbox: bool, peak_memory: &mut Option<u64>) -> Result<Result<wfrt::Value, wfrt::Failure>, WorkerError> {
+    let io_error = |e: io::Error| WorkerError::Internal(internal(e));
+    let mut command = Command::new(env::current_exe().map_err(io_error)?);
+    command
+        .arg("--worker")
+        .arg(backend.name())
+        .arg(path)
+        .args(sandbox.then_some("--sandbox"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let cpu = limits.cpu_seconds;
+    // SAFETY: `setrlimit` and `dup2` are async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            // the result goes to its own pipe, and stdout is stderr.
+            if libc::dup2(libc::STDOUT_FILENO, wfrt::alloc::RESULT_FD) < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // the hard limit is a second later, so that SIGXCPU is sent before SIGKILL.
+            set_rlimit(libc::RLIMIT_CPU, cpu, cpu + 1)?;
+            set_rlimit(libc::RLIMIT_AS, limits.memory, limits.memory)?;
+            // crashes should not leave core dumps behind.
+            set_rlimit(libc::RLIMIT_CORE, 0, 0)
+        });
+    }
+    let mut child = command.spawn().map_err(io_error)?;
+    let result = read_result(child.stdout.take().unwrap());
+    let output = read_tail(child.stderr.take().unwrap());
+    let mut stdin = child.stdin.take().unwrap();
+    // the worker might exit before reading all of its input, which is not an error here.
+    let _ = stdin.write_all(args);
+    drop(stdin);
+
+    let (status, killed, peak) = wait(&mut child, limits).map_err(io_error)?;
+    *peak_memory = Some(peak);
+    if killed {
+        return Err(WorkerError::TimeLimit(limits.time));
+    }
+    let result = result.join().unwrap_or_default().ok_or(WorkerError::ResultTooLarge)?;
+    let output = String::from_utf8_lossy(&output.join().unwrap_or_default()).into_owned();
+    if result == wfrt::alloc::OUT_OF_MEMORY {
+        return Err(WorkerError::MemoryLimit(limits.memory));
+    }
+    if status.success() {
+        let result: Output = wfrt::from_bytes(&result).map_err(|e| WorkerError::Internal(internal(e)))?;
+        return result.map_err(|e| WorkerError::Internal(ZError::generic(e)));
+    }
+    if status.signal() == Some(libc::SIGXCPU) {
+        return Err(WorkerError::CpuTimeLimit(Duration::from_secs(cpu)));
+    }
+    if sandbox && status.signal() == Some(libc::SIGSYS) {
+        return Err(WorkerError::SandboxViolation);
+    }
+    let last_line = output.lines().last().unwrap_or_default();
+    let reason = match (status.signal(), status.code()) {
+        (Some(signal), _) => format!("killed by signal {signal}"),
+        (_, Some(code)) => format!("exited with status {code}"),
+        _ => "exited without a result".to_owned(),
+    };
+    if last_line.is_empty() {
+        Err(WorkerError::Crashed(reason))
+    } else {
+        Err(WorkerError::Crashed(format!("{reason}: {last_line}")))
+    }
+}
+
+/// The main function of a worker, with the arguments after `--worker`.
+pub fn main(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let backend = Backend::from_name(&args.next().ok_or("missing backend")?)?;
+    let path = args.next().ok_or("missing path")?;
+    let sandbox = args.next().as_deref() == Some("--sandbox");
+
+    IS_WORKER.store(true, Ordering::Relaxed);
+    // SAFETY: the result pipe was set up by `run`, and is not used anywhere else.
+    let mut result_out = unsafe { File::from_raw_fd(wfrt::alloc::RESULT_FD) };
+
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let output: Output = backend.run(Path::new(&path), &input, sandbox).map_err(|e| e.to_string());
+    result_out.write_all(&wfrt::to_stdvec(&output)?)?;
+    Ok(())
+}
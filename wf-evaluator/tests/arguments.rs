@@ -0,0 +1,139 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{error_messages, evaluate};
+
+fn string(s: &str) -> Value {
+    json!({ "Z1K1": "Z6", "Z6K1": s })
+}
+
+#[test]
+pub fn arguments_bound_by_key() -> Result<(), Box<dyn Error>> {
+    // `Z1000K10` comes before `Z1000K2` in the map.
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K2: String, Z1000K10: String) -> String { format!(\"{Z1000K2}-{Z1000K10}\") }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K10": string("ten"), "Z1000K2": string("two") },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "two-ten");
+    Ok(())
+}
+
+#[test]
+pub fn missing_and_extra_arguments() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: String, Z1000K2: String) -> String { Z1000K1 + &Z1000K2 }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": string("one"), "Z1000K3": string("three") },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    let errors = result.to_string();
+    assert!(errors.contains(r#""Z511K1":"Z1000K2""#), "{errors}");
+    assert_eq!(
+        error_messages(&result),
+        ["unexpected argument Z1000K3: the function has no parameter with that name"]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn signature_checked_against_declaration() -> Result<(), Box<dyn Error>> {
+    let argument = |key: &str| json!({ "Z1K1": "Z17", "Z17K1": "Z6", "Z17K2": key, "Z17K3": { "Z1K1": "Z12", "Z12K1": ["Z11"] } });
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: String, Z1000K2: u32) -> bool { true }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": string("one"), "Z1000K2": string("two") },
+        "function": {
+            "Z1K1": "Z8",
+            "Z8K1": ["Z17", argument("Z1000K1"), argument("Z1000K2")],
+            "Z8K2": "Z6",
+            "Z8K3": ["Z20"],
+            "Z8K4": ["Z14"],
+            "Z8K5": "Z1000",
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    assert_eq!(
+        error_messages(&result),
+        [
+            "the argument Z1000K2 is a Z6/string, which is `String` in Rust, but the parameter is `Z1000K2: u32`",
+            "the function returns a Z6/string, which is `String` in Rust, but the implementation returns `bool`",
+        ]
+    );
+    let errors = result.to_string();
+    assert!(errors.contains(r#""Z506K3":"Z1000K2""#), "{errors}");
+    assert!(errors.contains(r#""Z517K2":"bool""#), "{errors}");
+    Ok(())
+}
+
+#[test]
+pub fn list_signature_checked_against_declaration() -> Result<(), Box<dyn Error>> {
+    let list_of_strings = json!({ "Z1K1": "Z7", "Z7K1": "Z881", "Z881K1": "Z6" });
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<bool>) -> Vec<String> { Vec::new() }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": ["Z6"] },
+        "function": {
+            "Z1K1": "Z8",
+            "Z8K1": ["Z17", { "Z1K1": "Z17", "Z17K1": list_of_strings, "Z17K2": "Z1000K1", "Z17K3": { "Z1K1": "Z12", "Z12K1": ["Z11"] } }],
+            "Z8K2": list_of_strings,
+            "Z8K3": ["Z20"],
+            "Z8K4": ["Z14"],
+            "Z8K5": "Z1000",
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(
+        error_messages(&result),
+        ["the argument Z1000K1 is a Z881/typed list of Z6/string, which is `Vec<String>` in Rust, but the parameter is `Z1000K1: Vec<bool>`"]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn aliases_left_to_rustc() -> Result<(), Box<dyn Error>> {
+    let argument = |key: &str, ty: &Value| json!({ "Z1K1": "Z17", "Z17K1": ty, "Z17K2": key, "Z17K3": { "Z1K1": "Z12", "Z12K1": ["Z11"] } });
+    let pair_type = json!({ "Z1K1": "Z7", "Z7K1": "Z882", "Z882K1": "Z6", "Z882K2": "Z40" });
+    let map_type = json!({ "Z1K1": "Z7", "Z7K1": "Z883", "Z883K1": "Z6", "Z883K2": "Z40" });
+    let input = json!({
+        "codeString": "type S = String;\ntype H = std::hash::RandomState;\nfn Z1000(Z1000K1: S, Z1000K2: std::collections::HashMap<String, bool, H>) -> S { Z1000K1 + &Z1000K2.len().to_string() }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": string("n"), "Z1000K2": { "Z1K1": map_type, "K1": [pair_type] } },
+        "function": {
+            "Z1K1": "Z8",
+            "Z8K1": ["Z17", argument("Z1000K1", &json!("Z6")), argument("Z1000K2", &map_type)],
+            "Z8K2": "Z6",
+            "Z8K3": ["Z20"],
+            "Z8K4": ["Z14"],
+            "Z8K5": "Z1000",
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "n0");
+    Ok(())
+}
+
+#[test]
+pub fn extra_parameter_reported_once() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: String, Z1000K2: String) -> String { Z1000K1 + &Z1000K2 }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": string("one") },
+        "function": {
+            "Z1K1": "Z8",
+            "Z8K1": ["Z17", { "Z1K1": "Z17", "Z17K1": "Z6", "Z17K2": "Z1000K1", "Z17K3": { "Z1K1": "Z12", "Z12K1": ["Z11"] } }],
+            "Z8K2": "Z6",
+            "Z8K3": ["Z20"],
+            "Z8K4": ["Z14"],
+            "Z8K5": "Z1000",
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(error_messages(&result), ["the parameter Z1000K2 is not an argument of the function"]);
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::evaluate;
+
+fn boolean(b: bool) -> Value {
+    json!({ "Z1K1": "Z40", "Z40K1": if b { "Z41" } else { "Z42" } })
+}
+
+#[test]
+pub fn boolean_arguments_and_result() -> Result<(), Box<dyn Error>> {
+    // the first argument is a Z40 in normal form, the second a reference to Z42/false.
+    let mut input: Value =
+        serde_json::from_str(include_str!("../../test_data/evaluator_input_boolean.json"))?;
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], boolean(true));
+
+    input["functionArguments"]["Z1000K2"] = boolean(true);
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], boolean(false));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+use common::{evaluate, metadata};
+
+fn input(n: u32) -> Value {
+    json!({
+        "codeString": format!("fn Z1000() -> String {{ \"{n}\".to_owned() }}"),
+        "functionName": "Z1000",
+        "functionArguments": {},
+    })
+}
+
+/// Evaluates `input` with the cache in `dir`, returning the result and whether
+/// the cache was hit.
+fn evaluate_cached(input: &Value, dir: &Path, env: &[(&str, &str)]) -> Result<(Value, String), Box<dyn Error>> {
+    let dir = dir.to_str().unwrap();
+    let env = [&[("WF_CACHE", "on"), ("WF_CACHE_DIR", dir)], env].concat();
+    let result = evaluate(input, &env)?;
+    let cache = metadata(&result, "compilationCache").unwrap().as_str().unwrap().to_owned();
+    Ok((result, cache))
+}
+
+fn entries(dir: &Path) -> usize {
+    fs::read_dir(dir).map_or(0, |entries| entries.count())
+}
+
+#[test]
+pub fn hit_and_miss() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let (result, cache) = evaluate_cached(&input(1), dir.path(), &[])?;
+    assert_eq!((&result["Z22K1"], cache.as_str()), (&json!("1"), "miss"));
+    let (result, cache) = evaluate_cached(&input(1), dir.path(), &[])?;
+    assert_eq!((&result["Z22K1"], cache.as_str()), (&json!("1"), "hit"));
+    assert!(metadata(&result, "compilationDuration").is_none());
+    let (result, cache) = evaluate_cached(&input(2), dir.path(), &[])?;
+    assert_eq!((&result["Z22K1"], cache.as_str()), (&json!("2"), "miss"));
+    assert_eq!(entries(dir.path()), 2);
+    Ok(())
+}
+
+#[test]
+pub fn least_recently_used_evicted() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let limit = [("WF_CACHE_MAX_ENTRIES", "2")];
+    for n in 1..=3 {
+        assert_eq!(evaluate_cached(&input(n), dir.path(), &limit)?.1, "miss");
+    }
+    assert_eq!(entries(dir.path()), 2);
+    // 1 was used least recently, so it was evicted.
+    assert_eq!(evaluate_cached(&input(3), dir.path(), &limit)?.1, "hit");
+    assert_eq!(evaluate_cached(&input(1), dir.path(), &limit)?.1, "miss");
+    // now 2 was.
+    assert_eq!(evaluate_cached(&input(3), dir.path(), &limit)?.1, "hit");
+    assert_eq!(evaluate_cached(&input(2), dir.path(), &limit)?.1, "miss");
+    assert_eq!(entries(dir.path()), 2);
+    Ok(())
+}
+
+#[test]
+pub fn disabled() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let env = [("WF_CACHE", "off"), ("WF_CACHE_DIR", dir.path().to_str().unwrap())];
+    for _ in 0..2 {
+        let result = evaluate(&input(1), &env)?;
+        assert_eq!(result["Z22K1"], "1");
+        assert!(metadata(&result, "compilationCache").is_none());
+        assert!(metadata(&result, "compilationDuration").is_some());
+    }
+    assert_eq!(entries(dir.path()), 0);
+    Ok(())
+}
+
+#[test]
+pub fn concurrent_eviction() -> Result<(), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    // every evaluator evicts the entries of the others.
+    let limit = [("WF_CACHE_MAX_ENTRIES", "1")];
+    std::thread::scope(|s| {
+        let threads: Vec<_> = (1..=4).map(|n| {
+            let dir = dir.path();
+            s.spawn(move || {
+                for _ in 0..3 {
+                    let (result, _) = evaluate_cached(&input(n), dir, &limit).unwrap();
+                    assert_eq!(result["Z22K1"], n.to_string());
+                }
+            })
+        }).collect();
+        threads.into_iter().try_for_each(|t| t.join())
+    }).map_err(|_| "an evaluation failed")?;
+    Ok(())
+}
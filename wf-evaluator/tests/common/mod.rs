@@ -0,0 +1,74 @@
+//! Running the evaluator from tests.
+
+// each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+fn workspace() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap()
+}
+
+/// A Z13518/natural number in canonical form.
+pub fn natural(n: &str) -> Value {
+    json!({ "Z1K1": "Z13518", "Z13518K1": n })
+}
+
+/// Evaluates one input, with `env` set for the evaluator, and returns its Z22.
+pub fn evaluate(input: &Value, env: &[(&str, &str)]) -> Result<Value, Box<dyn Error>> {
+    // the evaluated function is compiled against the runtime library in `target/debug`.
+    static WFRT: OnceLock<bool> = OnceLock::new();
+    let built = WFRT.get_or_init(|| {
+        Command::new(env!("CARGO"))
+            .args(["build", "-q", "-p", "wfrt"])
+            .current_dir(workspace())
+            .status()
+            .is_ok_and(|s| s.success())
+    });
+    assert!(built, "failed to build wfrt");
+
+    let mut evaluator = Command::new(env!("CARGO_BIN_EXE_wf-evaluator"))
+        .current_dir(workspace())
+        .env("WF_CACHE", "off")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    evaluator.stdin.take().unwrap().write_all(input.to_string().as_bytes())?;
+    let output = evaluator.wait_with_output()?;
+    assert!(output.status.success());
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// The value of `key` in the metadata of `result`.
+pub fn metadata<'a>(result: &'a Value, key: &str) -> Option<&'a Value> {
+    let entries = result["Z22K2"]["K1"].as_array()?;
+    entries.iter().find(|entry| entry["K1"] == key).map(|entry| &entry["K2"])
+}
+
+/// The error that the evaluation in `result` ran into, inside its Z507/error in evaluation.
+pub fn error(result: &Value) -> &Value {
+    let errors = metadata(result, "errors").expect("the evaluation succeeded");
+    assert_eq!(errors["Z5K1"], "Z507");
+    &errors["Z5K2"]["Z507K2"]
+}
+
+/// The messages of the Z500/generic errors in `value`.
+pub fn error_messages(value: &Value) -> Vec<&str> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .flat_map(|(key, value)| match (key.as_str(), value) {
+                ("Z500K1", Value::String(message)) => vec![message.as_str()],
+                _ => error_messages(value),
+            })
+            .collect(),
+        Value::Array(values) => values.iter().flat_map(error_messages).collect(),
+        _ => vec![],
+    }
+}
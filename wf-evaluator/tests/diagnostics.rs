@@ -0,0 +1,53 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{evaluate, natural};
+
+/// The error that the evaluation of `code` ran into.
+fn error(code: &str) -> Result<Value, Box<dyn Error>> {
+    let input = json!({
+        "codeString": code,
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    let entries = result["Z22K2"]["K1"].as_array().unwrap();
+    let errors = entries.iter().find(|entry| entry["K1"] == "errors").unwrap();
+    assert_eq!(errors["K2"]["Z5K1"], "Z507");
+    Ok(errors["K2"]["Z5K2"]["Z507K2"].clone())
+}
+
+#[test]
+pub fn type_error_location() -> Result<(), Box<dyn Error>> {
+    let error = error("fn Z1000() -> String {\n    let x: u32 = \"a\";\n    x.to_string()\n}")?;
+    assert_eq!(error["Z5K1"], "Z580");
+    let value = &error["Z5K2"];
+    let message = value["Z580K1"].as_str().unwrap();
+    assert!(message.starts_with("error[E0308]: mismatched types"), "{message}");
+    assert_eq!(value["Z580K2"], natural("2"));
+    assert_eq!(value["Z580K3"], natural("18"));
+    Ok(())
+}
+
+#[test]
+pub fn location_on_the_first_line() -> Result<(), Box<dyn Error>> {
+    // the first line of the code string is indented in the generated source.
+    let error = error("fn Z1000() -> u32 { \"a\" }")?;
+    assert_eq!(error["Z5K1"], "Z580");
+    assert_eq!(error["Z5K2"]["Z580K2"], natural("1"));
+    assert_eq!(error["Z5K2"]["Z580K3"], natural("21"));
+    Ok(())
+}
+
+#[test]
+pub fn several_errors() -> Result<(), Box<dyn Error>> {
+    let error = error("fn Z1000() -> u32 {\n    let a: u32 = \"a\";\n    let b: u32 = true;\n    a + b\n}")?;
+    assert_eq!(error["Z5K1"], "Z509");
+    let errors = error["Z5K2"]["Z509K1"].as_array().unwrap();
+    let lines: Vec<_> = errors[1..].iter().map(|e| &e["Z5K2"]["Z580K2"]).collect();
+    assert_eq!(lines, [&natural("2"), &natural("3")]);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+mod common;
+
+use common::{error, error_messages, evaluate, natural};
+
+fn input(code: &str) -> Value {
+    json!({
+        "codeString": code,
+        "functionName": "Z1000",
+        "functionArguments": {},
+    })
+}
+
+#[test]
+pub fn wall_clock_limit() -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let result = evaluate(
+        &input("fn Z1000() -> String { std::thread::sleep(std::time::Duration::from_secs(60)); String::new() }"),
+        &[("WF_TIME_LIMIT", "0.5")],
+    )?;
+    assert!(start.elapsed() < Duration::from_secs(30));
+    let error = error(&result);
+    assert_eq!(error["Z5K1"], "Z581");
+    assert_eq!(error["Z5K2"]["Z581K1"], natural("500"));
+    Ok(())
+}
+
+#[test]
+pub fn cpu_time_limit() -> Result<(), Box<dyn Error>> {
+    let result = evaluate(
+        &input("fn Z1000() -> String { loop { std::hint::black_box(0); } }"),
+        &[("WF_TIME_LIMIT", "30"), ("WF_CPU_TIME_LIMIT", "1")],
+    )?;
+    let error = error(&result);
+    assert_eq!(error["Z5K1"], "Z582");
+    assert_eq!(error["Z5K2"]["Z582K1"], natural("1"));
+    Ok(())
+}
+
+#[test]
+pub fn memory_limit() -> Result<(), Box<dyn Error>> {
+    let limit = (512u64 << 20).to_string();
+    let result = evaluate(
+        &input("fn Z1000() -> usize { std::hint::black_box(vec![1u8; 1 << 30]).len() }"),
+        &[("WF_MEMORY_LIMIT", &limit)],
+    )?;
+    assert_eq!(error(&result)["Z5K1"], "Z583");
+    assert_eq!(error(&result)["Z5K2"]["Z583K1"], natural(&limit));
+
+    // fallible allocations are out of memory too.
+    let result = evaluate(
+        &input("fn Z1000() -> bool { Vec::<u8>::new().try_reserve(1 << 30).is_ok() }"),
+        &[("WF_MEMORY_LIMIT", &limit)],
+    )?;
+    assert_eq!(error(&result)["Z5K1"], "Z583");
+    Ok(())
+}
+
+#[test]
+pub fn crash_is_not_a_memory_limit() -> Result<(), Box<dyn Error>> {
+    // what the standard library prints when an allocation fails does not make it one.
+    let result = evaluate(
+        &input("fn Z1000() -> String { eprintln!(\"memory allocation of 1024 bytes failed\"); std::process::abort() }"),
+        &[],
+    )?;
+    assert_eq!(error(&result)["Z5K1"], "Z500");
+    let messages = error_messages(&result);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("the function crashed: killed by signal 6"), "{messages:?}");
+    Ok(())
+}
+
+#[test]
+pub fn exit_status_is_not_a_memory_limit() -> Result<(), Box<dyn Error>> {
+    // the status that used to mean running out of memory.
+    let result = evaluate(&input("fn Z1000() -> String { std::process::exit(86) }"), &[])?;
+    assert_eq!(error(&result)["Z5K1"], "Z500");
+    assert_eq!(error_messages(&result), ["the function crashed: exited with status 86"]);
+    Ok(())
+}
+
+#[test]
+pub fn long_output() -> Result<(), Box<dyn Error>> {
+    // only the end of the output is kept.
+    let result = evaluate(
+        &input("fn Z1000() -> String { let s = \"x\".repeat(1 << 20); for _ in 0..256 { eprint!(\"{s}\"); } eprintln!(\"\\nlast\"); std::process::abort() }"),
+        &[],
+    )?;
+    assert_eq!(error_messages(&result), ["the function crashed: killed by signal 6: last"]);
+    Ok(())
+}
+
+#[test]
+pub fn result_too_large() -> Result<(), Box<dyn Error>> {
+    let result = evaluate(&input("fn Z1000() -> String { \"x\".repeat(100 << 20) }"), &[])?;
+    assert_eq!(error(&result)["Z5K1"], "Z500");
+    assert_eq!(error_messages(&result), ["the result is larger than 64 MiB"]);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs;
+
+mod common;
+
+use common::{error_messages, evaluate};
+
+fn reverse(argument: Value) -> Value {
+    json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<String>) -> Vec<String> { Z1000K1.into_iter().rev().collect() }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": argument },
+    })
+}
+
+#[test]
+pub fn list_in_normal_form() -> Result<(), Box<dyn Error>> {
+    let list: Value = serde_json::from_str(&fs::read_to_string("../test_data/list_string.json")?)?;
+    let result = evaluate(&reverse(list), &[])?;
+    assert_eq!(result["Z22K1"], json!(["Z6", "2", "1"]));
+    Ok(())
+}
+
+#[test]
+pub fn list_in_canonical_form() -> Result<(), Box<dyn Error>> {
+    let list = json!(["Z6", "a", { "Z1K1": "Z6", "Z6K1": "b" }]);
+    let result = evaluate(&reverse(list), &[])?;
+    assert_eq!(result["Z22K1"], json!(["Z6", "b", "a"]));
+    Ok(())
+}
+
+#[test]
+pub fn nested_and_empty_lists() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<bool>) -> Vec<Vec<bool>> { vec![Z1000K1, Vec::new()] }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": ["Z40", "Z41"] },
+    });
+    let result = evaluate(&input, &[])?;
+    let list_of_booleans = json!({ "Z1K1": "Z7", "Z7K1": "Z881", "Z881K1": "Z40" });
+    assert_eq!(
+        result["Z22K1"],
+        json!([list_of_booleans, ["Z40", { "Z1K1": "Z40", "Z40K1": "Z41" }], ["Z40"]])
+    );
+    Ok(())
+}
+
+#[test]
+pub fn list_element_type_mismatch() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<bool>) -> bool { true }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": ["Z6", "a"] },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    assert_eq!(error_messages(&result), ["expected Boolean, found String"]);
+    Ok(())
+}
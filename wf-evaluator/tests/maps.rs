@@ -0,0 +1,87 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{error, evaluate};
+
+fn pair_type() -> Value {
+    json!({ "Z1K1": "Z7", "Z7K1": "Z882", "Z882K1": "Z6", "Z882K2": "Z40" })
+}
+
+/// A map from strings to booleans, in canonical form.
+fn map(entries: &[(&str, bool)]) -> Value {
+    let pairs = entries.iter().map(|&(key, value)| {
+        let value = json!({ "Z1K1": "Z40", "Z40K1": if value { "Z41" } else { "Z42" } });
+        json!({ "Z1K1": pair_type(), "K1": key, "K2": value })
+    });
+    json!({
+        "Z1K1": { "Z1K1": "Z7", "Z7K1": "Z883", "Z883K1": "Z6", "Z883K2": "Z40" },
+        "K1": std::iter::once(pair_type()).chain(pairs).collect::<Vec<_>>(),
+    })
+}
+
+#[test]
+pub fn hash_map_entries_sorted_by_key() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: std::collections::HashMap<String, bool>) -> std::collections::HashMap<String, bool> { Z1000K1.into_iter().map(|(k, v)| (k, !v)).collect() }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": map(&[("c", true), ("a", false), ("b", true)]) },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], map(&[("a", true), ("b", false), ("c", false)]));
+    Ok(())
+}
+
+#[test]
+pub fn map_in_normal_form() -> Result<(), Box<dyn Error>> {
+    let reference = |id: &str| json!({ "Z1K1": "Z9", "Z9K1": id });
+    let pair_type = json!({ "Z1K1": reference("Z7"), "Z7K1": reference("Z882"), "Z882K1": reference("Z6"), "Z882K2": reference("Z40") });
+    let list_type = json!({ "Z1K1": reference("Z7"), "Z7K1": reference("Z881"), "Z881K1": pair_type });
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: std::collections::BTreeMap<String, bool>) -> Vec<String> { Z1000K1.into_keys().collect() }",
+        "functionName": "Z1000",
+        "functionArguments": {
+            "Z1000K1": {
+                "Z1K1": { "Z1K1": reference("Z7"), "Z7K1": reference("Z883"), "Z883K1": reference("Z6"), "Z883K2": reference("Z40") },
+                "K1": {
+                    "Z1K1": list_type,
+                    "K1": { "Z1K1": pair_type, "K1": { "Z1K1": "Z6", "Z6K1": "key" }, "K2": reference("Z41") },
+                    "K2": { "Z1K1": list_type },
+                },
+            },
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], json!(["Z6", "key"]));
+    Ok(())
+}
+
+#[test]
+pub fn empty_map_keeps_its_type() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000() -> std::collections::BTreeMap<String, bool> { Default::default() }",
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], map(&[]));
+    Ok(())
+}
+
+#[test]
+pub fn duplicate_key_rejected() -> Result<(), Box<dyn Error>> {
+    for map_type in ["std::collections::BTreeMap", "std::collections::HashMap"] {
+        let input = json!({
+            "codeString": format!("fn Z1000(Z1000K1: {map_type}<String, bool>) -> usize {{ Z1000K1.len() }}"),
+            "functionName": "Z1000",
+            "functionArguments": { "Z1000K1": map(&[("a", true), ("a", false)]) },
+        });
+        let result = evaluate(&input, &[])?;
+        let error = error(&result);
+        assert_eq!(error["Z5K1"], "Z516", "{map_type}");
+        assert_eq!(error["Z5K2"]["Z516K1"], "Z1000K1");
+        assert_eq!(error["Z5K2"]["Z516K2"], "a");
+    }
+    Ok(())
+}
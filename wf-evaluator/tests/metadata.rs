@@ -0,0 +1,28 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{evaluate, metadata};
+
+fn mebibytes(value: &Value) -> f64 {
+    value.as_str().unwrap().strip_suffix(" MiB").unwrap().parse().unwrap()
+}
+
+#[test]
+pub fn peak_memory_of_the_worker() -> Result<(), Box<dyn Error>> {
+    let input = |mebibytes: usize| {
+        json!({
+            "codeString": format!("fn Z1000() -> usize {{ std::hint::black_box(vec![1u8; {mebibytes} << 20]).iter().map(|&b| b as usize).sum() }}"),
+            "functionName": "Z1000",
+            "functionArguments": {},
+        })
+    };
+    let small = evaluate(&input(1), &[])?;
+    let large = evaluate(&input(256), &[])?;
+    let small = mebibytes(metadata(&small, "peakMemoryUsage").unwrap());
+    let large = mebibytes(metadata(&large, "peakMemoryUsage").unwrap());
+    assert!(large >= 256.0, "{large} MiB");
+    assert!(large - small >= 200.0, "{small} MiB, {large} MiB");
+    Ok(())
+}
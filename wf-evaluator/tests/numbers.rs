@@ -0,0 +1,100 @@
+use serde_json::json;
+use std::error::Error;
+
+mod common;
+
+use common::{evaluate, metadata, natural};
+
+#[test]
+pub fn integers_and_big_naturals() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: i64, Z1000K2: wfrt::num_bigint::BigUint) -> (i64, wfrt::num_bigint::BigUint) { (-Z1000K1, Z1000K2 * 2u32) }",
+        "functionName": "Z1000",
+        "functionArguments": {
+            "Z1000K1": { "Z1K1": "Z16683", "Z16683K1": "Z16660", "Z16683K2": natural("7") },
+            "Z1000K2": natural("18446744073709551616"),
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(
+        result["Z22K1"]["K1"],
+        json!({ "Z1K1": "Z16683", "Z16683K1": "Z16662", "Z16683K2": natural("7") })
+    );
+    assert_eq!(result["Z22K1"]["K2"], natural("36893488147419103232"));
+    Ok(())
+}
+
+#[test]
+pub fn out_of_range_argument() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: u8) -> u8 { Z1000K1 }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": natural("256") },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    let entries = result["Z22K2"]["K1"].as_array().unwrap();
+    let errors = entries.iter().find(|entry| entry["K1"] == "errors").unwrap();
+    let error = &errors["K2"]["Z5K2"]["Z507K2"];
+    assert_eq!(error["Z5K1"], "Z516");
+    assert_eq!(error["Z5K2"]["Z516K1"], "Z1000K1");
+    assert_eq!(error["Z5K2"]["Z516K2"], natural("256"));
+    Ok(())
+}
+
+#[test]
+pub fn ill_formed_integer_argument() -> Result<(), Box<dyn Error>> {
+    // zero, and only zero, has the neutral sign.
+    for (sign, abs) in [("Z16661", "5"), ("Z16660", "0"), ("Z16662", "0")] {
+        let input = json!({
+            "codeString": "fn Z1000(Z1000K1: i64) -> i64 { Z1000K1 }",
+            "functionName": "Z1000",
+            "functionArguments": {
+                "Z1000K1": { "Z1K1": "Z16683", "Z16683K1": sign, "Z16683K2": natural(abs) },
+            },
+        });
+        let result = evaluate(&input, &[])?;
+        assert_eq!(result["Z22K1"], "Z24");
+        // the arguments are rejected before the function is called.
+        let error = metadata(&result, "errors").unwrap();
+        assert_eq!(error["Z5K1"], "Z502", "{sign} {abs}");
+        assert_eq!(error["Z5K2"]["Z502K1"], "Z16683/integer");
+        assert_eq!(error["Z5K2"]["Z502K2"], json!({ "Z1K1": "Z16683", "Z16683K1": sign, "Z16683K2": natural(abs) }));
+    }
+    Ok(())
+}
+
+#[test]
+pub fn ill_formed_natural_argument() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<u64>) -> usize { Z1000K1.len() }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": ["Z13518", natural("1"), natural("-2")] },
+    });
+    let result = evaluate(&input, &[])?;
+    let error = metadata(&result, "errors").unwrap();
+    assert_eq!(error["Z5K1"], "Z502");
+    assert_eq!(error["Z5K2"]["Z502K1"], "Z13518/natural number");
+    assert_eq!(error["Z5K2"]["Z502K2"], "-2");
+    Ok(())
+}
+
+#[test]
+pub fn special_floats_round_trip() -> Result<(), Box<dyn Error>> {
+    let returned = evaluate(
+        &json!({
+            "codeString": "fn Z1000() -> Vec<f64> { vec![-0.0, f64::NAN, f64::NEG_INFINITY, 5e-324] }",
+            "functionName": "Z1000",
+            "functionArguments": {},
+        }),
+        &[],
+    )?;
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<f64>) -> String { format!(\"{:?} {}\", Z1000K1, Z1000K1[0].is_sign_negative()) }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": returned["Z22K1"] },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], "[-0.0, NaN, -inf, 5e-324] true");
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::evaluate;
+
+fn identity(argument: Value) -> Value {
+    json!({
+        "codeString": "fn Z1000(Z1000K1: (String, Vec<bool>)) -> (String, Vec<bool>) { Z1000K1 }",
+        "functionName": "Z1000",
+        "functionArguments": { "Z1000K1": argument },
+    })
+}
+
+#[test]
+pub fn pair_round_trip() -> Result<(), Box<dyn Error>> {
+    let pair = json!({
+        "Z1K1": {
+            "Z1K1": "Z7",
+            "Z7K1": "Z882",
+            "Z882K1": "Z6",
+            "Z882K2": { "Z1K1": "Z7", "Z7K1": "Z881", "Z881K1": "Z40" },
+        },
+        "K1": "a",
+        "K2": ["Z40", { "Z1K1": "Z40", "Z40K1": "Z41" }],
+    });
+    let result = evaluate(&identity(pair.clone()), &[])?;
+    assert_eq!(result["Z22K1"], pair);
+    Ok(())
+}
+
+#[test]
+pub fn pair_in_normal_form() -> Result<(), Box<dyn Error>> {
+    let reference = |id: &str| json!({ "Z1K1": "Z9", "Z9K1": id });
+    let list_type = json!({ "Z1K1": reference("Z7"), "Z7K1": reference("Z881"), "Z881K1": reference("Z40") });
+    let pair = json!({
+        "Z1K1": { "Z1K1": reference("Z7"), "Z7K1": reference("Z882"), "Z882K1": reference("Z6"), "Z882K2": list_type },
+        "K1": { "Z1K1": "Z6", "Z6K1": "a" },
+        "K2": { "Z1K1": list_type },
+    });
+    let result = evaluate(&identity(pair), &[])?;
+    assert_eq!(result["Z22K1"]["K1"], "a");
+    assert_eq!(result["Z22K1"]["K2"], json!(["Z40"]));
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use serde_json::json;
+use std::error::Error;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+
+use common::{error, evaluate, natural};
+
+#[test]
+pub fn panic_message_and_backtrace() -> Result<(), Box<dyn Error>> {
+    let input = json!({
+        "codeString": "fn Z1000() -> String {\n    panic!(\"boom\")\n}",
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate(&input, &[("RUST_BACKTRACE", "1")])?;
+    assert_eq!(result["Z22K1"], "Z24");
+    assert_eq!(error(&result)["Z5K1"], "Z585");
+    let value = &error(&result)["Z5K2"];
+    assert_eq!(value["Z585K1"], "boom");
+    assert_eq!(value["Z585K2"], natural("2"));
+    assert_eq!(value["Z585K3"], natural("5"));
+    // the backtrace ends at the implementation, without the template and the evaluator below it.
+    let backtrace = value["Z585K4"].as_str().unwrap();
+    let last = backtrace.lines().last().unwrap();
+    assert!(last.ends_with("::Z1000"), "{backtrace}");
+    assert!(!backtrace.contains("evaluate_inner"), "{backtrace}");
+    assert!(!backtrace.contains("evaluate_entrypoint::{{closure}}"), "{backtrace}");
+
+    // without `RUST_BACKTRACE`, there is no backtrace.
+    let result = evaluate(&input, &[("RUST_BACKTRACE", "0")])?;
+    let value = &error(&result)["Z5K2"];
+    assert_eq!(value["Z585K1"], "boom");
+    assert!(value.get("Z585K4").is_none(), "{value}");
+    Ok(())
+}
+
+#[test]
+pub fn previous_panic_hook_restored() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    panic::set_hook(Box::new(|_| {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let caught = wfrt::catch_panic(|| panic!("caught"));
+    assert_eq!(caught.unwrap_err().message, "caught");
+    assert_eq!(CALLS.load(Ordering::SeqCst), 0, "the hook was called while catching the panic");
+
+    assert!(panic::catch_unwind(|| panic!("after")).is_err());
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1, "the hook was not restored");
+    let _ = panic::take_hook();
+}
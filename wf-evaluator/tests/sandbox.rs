@@ -0,0 +1,119 @@
+use serde_json::{json, Value};
+use std::error::Error;
+
+mod common;
+
+use common::{error, evaluate};
+
+/// Evaluates `code`, a function `f` without arguments, in the sandbox.
+fn evaluate_sandboxed(code: &str) -> Result<Value, Box<dyn Error>> {
+    let input = json!({
+        "codeString": code,
+        "functionName": "f",
+        "functionArguments": {},
+    });
+    evaluate(&input, &[("WF_SANDBOX", "on")])
+}
+
+fn assert_violation(result: &Value) {
+    assert_eq!(result["Z22K1"], "Z24");
+    let error = error(result);
+    assert_eq!(error["Z5K1"], "Z584", "expected a sandbox violation, got {error}");
+}
+
+#[test]
+pub fn sandbox_allows_computation() -> Result<(), Box<dyn Error>> {
+    let result = evaluate_sandboxed(
+        "fn f() -> String { std::thread::spawn(|| (1..=10).sum::<u32>()).join().unwrap().to_string() }",
+    )?;
+    assert_eq!(result["Z22K1"], "55");
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_denies_file_writes() -> Result<(), Box<dyn Error>> {
+    let path = std::env::temp_dir().join("wf-evaluator-sandbox-test");
+    let code = format!(
+        "fn f() -> String {{ std::fs::write({path:?}, \"written\").unwrap(); String::new() }}"
+    );
+    let result = evaluate_sandboxed(&code)?;
+    assert_violation(&result);
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_denies_processes() -> Result<(), Box<dyn Error>> {
+    let result = evaluate_sandboxed(
+        "fn f() -> String { std::process::Command::new(\"true\").status().unwrap(); String::new() }",
+    )?;
+    assert_violation(&result);
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_denies_sockets() -> Result<(), Box<dyn Error>> {
+    let result = evaluate_sandboxed(
+        "fn f() -> String { std::net::UdpSocket::bind(\"127.0.0.1:0\").unwrap(); String::new() }",
+    )?;
+    assert_violation(&result);
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_covers_static_constructors() -> Result<(), Box<dyn Error>> {
+    // constructors run when the implementation is loaded, before the function is called.
+    let path = std::env::temp_dir().join("wf-evaluator-sandbox-constructor-test");
+    let code = format!(
+        "fn f() -> String {{ String::new() }}
+        #[used]
+        #[link_section = \".init_array\"]
+        static CONSTRUCTOR: extern \"C\" fn() = {{
+            extern \"C\" fn constructor() {{ let _ = std::fs::write({path:?}, \"escaped\"); }}
+            constructor
+        }};"
+    );
+    let result = evaluate_sandboxed(&code)?;
+    assert_violation(&result);
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_hides_files() -> Result<(), Box<dyn Error>> {
+    // the only file in the sandbox is the implementation itself.
+    let cargo_toml = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let code = format!(
+        "fn f() -> String {{
+            let read = [\"/etc/hostname\", {cargo_toml:?}].map(|path| std::fs::read(path).is_ok());
+            let root: Vec<_> = std::fs::read_dir(\"/\").unwrap().map(|e| e.unwrap().file_name()).collect();
+            format!(\"{{read:?}} {{root:?}}\")
+        }}"
+    );
+    let result = evaluate_sandboxed(&code)?;
+    assert_eq!(result["Z22K1"], "[false, false] [\"implementation.so\"]");
+    Ok(())
+}
+
+#[test]
+pub fn sandbox_denies_signalling_other_processes() -> Result<(), Box<dyn Error>> {
+    // the parent is the evaluator, which `evaluate` checks is still there to exit successfully.
+    for target in ["getppid()", "0", "-1"] {
+        let code = format!(
+            "fn f() -> String {{
+                extern \"C\" {{
+                    fn getppid() -> i32;
+                    fn kill(pid: i32, signal: i32) -> i32;
+                }}
+                unsafe {{ kill({target}, 9) }};
+                String::new()
+            }}"
+        );
+        let result = evaluate_sandboxed(&code)?;
+        assert_violation(&result);
+    }
+    // aborting sends a signal to the worker itself, which is allowed.
+    let result = evaluate_sandboxed("fn f() -> String { std::process::abort() }")?;
+    assert_eq!(error(&result)["Z5K1"], "Z500");
+    Ok(())
+}
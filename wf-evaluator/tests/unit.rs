@@ -0,0 +1,35 @@
+use serde_json::json;
+use std::error::Error;
+
+mod common;
+
+use common::{evaluate, natural};
+
+#[test]
+pub fn unit_returned_as_z21() -> Result<(), Box<dyn Error>> {
+    // a function without a return type returns `()`.
+    let input = json!({
+        "codeString": "fn Z1000() {}",
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], json!({ "Z1K1": "Z21" }));
+    Ok(())
+}
+
+#[test]
+pub fn unit_arguments() -> Result<(), Box<dyn Error>> {
+    // a unit can also be passed as a reference to Z24/void, its persistent instance.
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: (), Z1000K2: Vec<()>) -> usize { Z1000K2.len() }",
+        "functionName": "Z1000",
+        "functionArguments": {
+            "Z1000K1": { "Z1K1": "Z21" },
+            "Z1000K2": ["Z21", "Z24", { "Z1K1": "Z21" }],
+        },
+    });
+    let result = evaluate(&input, &[])?;
+    assert_eq!(result["Z22K1"], natural("2"));
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! The WebAssembly backend. These tests only run when the `wasm32-wasip1` target
+//! is installed, and pass without doing anything otherwise.
+
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+mod common;
+
+use common::{error, evaluate, natural};
+
+const TARGET: &str = "wasm32-wasip1";
+
+/// Whether the target is installed, building wfrt for it if it is.
+fn target_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let libdir = Command::new("rustc")
+            .args(["--print", "target-libdir", "--target", TARGET])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok());
+        if !libdir.is_some_and(|dir| Path::new(dir.trim()).is_dir()) {
+            eprintln!("skipping: the {TARGET} target is not installed");
+            return false;
+        }
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let built = Command::new(env!("CARGO"))
+            .args(["build", "-q", "-p", "wfrt", "--target", TARGET])
+            .current_dir(workspace)
+            .status()
+            .is_ok_and(|s| s.success());
+        assert!(built, "failed to build wfrt for {TARGET}");
+        true
+    })
+}
+
+fn evaluate_wasm(input: &Value) -> Result<Value, Box<dyn Error>> {
+    evaluate(input, &[("WF_BACKEND", "wasm")])
+}
+
+#[test]
+pub fn round_trip() -> Result<(), Box<dyn Error>> {
+    if !target_available() {
+        return Ok(());
+    }
+    let input = json!({
+        "codeString": "fn Z1000(Z1000K1: Vec<String>, Z1000K2: bool) -> (String, bool) { (Z1000K1.concat(), !Z1000K2) }",
+        "functionName": "Z1000",
+        "functionArguments": {
+            "Z1000K1": ["Z6", "a", "b"],
+            "Z1000K2": { "Z1K1": "Z40", "Z40K1": "Z42" },
+        },
+    });
+    let result = evaluate_wasm(&input)?;
+    assert_eq!(result["Z22K1"]["K1"], "ab");
+    assert_eq!(result["Z22K1"]["K2"], json!({ "Z1K1": "Z40", "Z40K1": "Z41" }));
+    Ok(())
+}
+
+#[test]
+pub fn panic() -> Result<(), Box<dyn Error>> {
+    if !target_available() {
+        return Ok(());
+    }
+    // panics abort on WebAssembly, so the panic only reaches the evaluator as the
+    // JSON that the panic hook writes to stderr before the module traps.
+    let input = json!({
+        "codeString": "fn Z1000() -> String {\n    eprintln!(\"not a panic\");\n    panic!(\"boom\")\n}",
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate_wasm(&input)?;
+    assert_eq!(result["Z22K1"], "Z24");
+    let error = error(&result);
+    assert_eq!(error["Z5K1"], "Z585");
+    assert_eq!(error["Z5K2"]["Z585K1"], "boom");
+    assert_eq!(error["Z5K2"]["Z585K2"], natural("3"));
+    assert_eq!(error["Z5K2"]["Z585K3"], natural("5"));
+    Ok(())
+}
+
+#[test]
+pub fn memory_limit() -> Result<(), Box<dyn Error>> {
+    if !target_available() {
+        return Ok(());
+    }
+    let input = json!({
+        "codeString": "fn Z1000() -> usize { std::hint::black_box(vec![1u8; 1 << 30]).len() }",
+        "functionName": "Z1000",
+        "functionArguments": {},
+    });
+    let result = evaluate(&input, &[("WF_BACKEND", "wasm"), ("WF_MEMORY_LIMIT", "536870912")])?;
+    assert_eq!(error(&result)["Z5K1"], "Z583");
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! The global allocator of compiled implementations and of the worker that runs
+//! them, which reports a failed allocation to the evaluator through the worker's
+//! result pipe, so that the evaluator can tell running out of memory apart from
+//! other crashes.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// The file descriptor of the worker's result pipe.
+pub const RESULT_FD: i32 = 3;
+
+/// What is written to [`RESULT_FD`] instead of the result when an allocation fails.
+/// It is never the start of a postcard-encoded result, whose first byte is 0 or 1.
+pub const OUT_OF_MEMORY: &[u8] = b"out of memory";
+
+/// Writes [`OUT_OF_MEMORY`] to the result pipe and aborts, without allocating.
+#[cfg(unix)]
+pub fn out_of_memory() -> ! {
+    use std::fs::File;
+    use std::io::Write;
+    use std::mem::ManuallyDrop;
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: the file is never closed, so it does not matter whether the descriptor is open.
+    let mut result = ManuallyDrop::new(unsafe { File::from_raw_fd(RESULT_FD) });
+    let _ = result.write_all(OUT_OF_MEMORY);
+    std::process::abort()
+}
+
+/// The system allocator, calling [`out_of_memory`] instead of returning null.
+/// Fallible allocations such as `Vec::try_reserve` end the process as well.
+///
+/// On WebAssembly it is the system allocator, and the evaluator notices that the
+/// memory of the module could not grow.
+pub struct Allocator;
+
+fn check(ptr: *mut u8) -> *mut u8 {
+    #[cfg(unix)]
+    if ptr.is_null() {
+        out_of_memory();
+    }
+    ptr
+}
+
+// SAFETY: everything is passed on to `System`, which upholds the contract.
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        check(System.alloc(layout))
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        check(System.alloc_zeroed(layout))
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        check(System.realloc(ptr, layout, new_size))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
@@ -56,3 +56,28 @@ impl Drop for OwnedBytes {
         drop(unsafe { Vec::from_raw_parts(self.ptr, self.len, self.len) })
     }
 }
+
+/// Entry points for implementations compiled to WebAssembly. The evaluator cannot
+/// pass pointers into its own memory there, so it allocates the arguments in the
+/// memory of the module with `alloc`, and gets the pointer and length of the
+/// result back packed into a `u64`.
+#[cfg(target_arch = "wasm32")]
+pub mod wasm {
+    use super::{Bytes, Function};
+    use std::mem::ManuallyDrop;
+
+    pub fn alloc(len: usize) -> *mut u8 {
+        ManuallyDrop::new(vec![0; len].into_boxed_slice()).as_mut_ptr()
+    }
+
+    /// Calls `f` with the `len` bytes at `ptr`, which must have been allocated with `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` and `len` must be from a call to `alloc`, and are not valid afterwards.
+    pub unsafe fn call(f: Function, ptr: *mut u8, len: usize) -> u64 {
+        let input = Vec::from_raw_parts(ptr, len, len);
+        let output = ManuallyDrop::new(f(Bytes::from_slice(&input)).into_vec());
+        ((output.as_ptr() as u64) << 32) | output.len() as u64
+    }
+}
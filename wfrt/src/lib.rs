@@ -1,15 +1,37 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::hash::{BuildHasher, Hash};
 
+use num_bigint::{BigInt, BigUint};
 
+pub mod alloc;
 pub mod ffi;
+pub mod panic;
+
+pub use alloc::Allocator;
+pub use panic::{catch_panic, Panic};
 
 // re-exports for use by compiled programs
+pub use num_bigint;
 pub use postcard::{from_bytes, to_stdvec};
 
 pub fn bytes_to_values(b: ffi::Bytes<'_>) -> postcard::Result<Vec<Value>> {
     postcard::from_bytes(b.as_slice())
 }
 
+/// Why an evaluated function did not return a value.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub enum Failure {
+    /// The arguments could not be read.
+    Arguments(String),
+    /// The argument at `index` is, or contains, the number `value`, which does not
+    /// fit in the type of its parameter.
+    OutOfRange { index: usize, value: Value },
+    /// The argument at `index` is, or contains, a map that has the key `key` more than once.
+    DuplicateKey { index: usize, key: Value },
+    Panic(Panic),
+}
+
 pub struct ExpectedFound {
     pub expected: &'static str,
     pub found: &'static str,
@@ -21,32 +43,367 @@ impl fmt::Display for ExpectedFound {
     }
 }
 
+/// Why a [`Value`] could not be converted into a Rust value.
+pub enum ConversionError {
+    ExpectedFound(ExpectedFound),
+    /// The value is a number that does not fit in the Rust type `ty`.
+    OutOfRange { value: Value, ty: &'static str },
+    /// The value is a map that has the key `key` more than once.
+    DuplicateKey(Value),
+}
+
+impl ConversionError {
+    /// The failure for the argument at `index` not converting.
+    pub fn into_failure(self, index: usize) -> Failure {
+        match self {
+            ConversionError::ExpectedFound(e) => Failure::Arguments(e.to_string()),
+            ConversionError::OutOfRange { value, .. } => Failure::OutOfRange { index, value },
+            ConversionError::DuplicateKey(key) => Failure::DuplicateKey { index, key },
+        }
+    }
+}
+
+impl From<ExpectedFound> for ConversionError {
+    fn from(e: ExpectedFound) -> Self {
+        ConversionError::ExpectedFound(e)
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::ExpectedFound(e) => e.fmt(f),
+            ConversionError::OutOfRange { value: Value::Natural(n), ty } => write!(f, "{n} is out of range for {ty}"),
+            ConversionError::OutOfRange { value: Value::Integer(n), ty } => write!(f, "{n} is out of range for {ty}"),
+            ConversionError::OutOfRange { value, ty } => write!(f, "the {} is out of range for {ty}", value.type_name()),
+            ConversionError::DuplicateKey(key) => write!(f, "the map has a {} key more than once", key.type_name()),
+        }
+    }
+}
+
+/// The type of a [`Value`]. Lists and maps carry the types of their elements, so
+/// that an empty one can still be turned into a typed ZObject.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    Unit,
+    String,
+    Boolean,
+    List(Box<ValueType>),
+    Pair(Box<ValueType>, Box<ValueType>),
+    Map(Box<ValueType>, Box<ValueType>),
+    Natural,
+    Integer,
+    Float,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub enum Value {
+    Unit,
     String(String),
+    Boolean(bool),
+    List(ValueType, Vec<Value>),
+    Pair(Box<Value>, Box<Value>),
+    /// The key type, the value type and the entries.
+    Map(ValueType, ValueType, Vec<(Value, Value)>),
+    Natural(BigUint),
+    Integer(BigInt),
+    Float(f64),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "Unit",
+            Value::String(_) => "String",
+            Value::Boolean(_) => "Boolean",
+            Value::List(..) => "List",
+            Value::Pair(..) => "Pair",
+            Value::Map(..) => "Map",
+            Value::Natural(_) => "Natural",
+            Value::Integer(_) => "Integer",
+            Value::Float(_) => "Float",
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Unit => ValueType::Unit,
+            Value::String(_) => ValueType::String,
+            Value::Boolean(_) => ValueType::Boolean,
+            Value::List(elem, _) => ValueType::List(Box::new(elem.clone())),
+            Value::Pair(first, second) => ValueType::Pair(Box::new(first.value_type()), Box::new(second.value_type())),
+            Value::Map(key, value, _) => ValueType::Map(Box::new(key.clone()), Box::new(value.clone())),
+            Value::Natural(_) => ValueType::Natural,
+            Value::Integer(_) => ValueType::Integer,
+            Value::Float(_) => ValueType::Float,
+        }
+    }
+}
+
+impl TryFrom<Value> for () {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Unit => Ok(()),
+            v => Err(ExpectedFound { expected: "Unit", found: v.type_name() }.into()),
+        }
+    }
 }
 
 impl TryFrom<Value> for String {
-    type Error = ExpectedFound;
+    type Error = ConversionError;
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
             Value::String(s) => Ok(s),
+            v => Err(ExpectedFound { expected: "String", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            v => Err(ExpectedFound { expected: "Boolean", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl<T: TryFrom<Value, Error = ConversionError>> TryFrom<Value> for Vec<T> {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::List(_, items) => items.into_iter().map(T::try_from).collect(),
+            v => Err(ExpectedFound { expected: "List", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl<A, B> TryFrom<Value> for (A, B)
+where
+    A: TryFrom<Value, Error = ConversionError>,
+    B: TryFrom<Value, Error = ConversionError>,
+{
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Pair(first, second) => Ok((A::try_from(*first)?, B::try_from(*second)?)),
+            v => Err(ExpectedFound { expected: "Pair", found: v.type_name() }.into()),
         }
     }
 }
 
+/// Converts the entries of a map and collects them with `insert`, which returns
+/// whether the key was new.
+fn collect_map<K, V, M>(value: Value, mut map: M, mut insert: impl FnMut(&mut M, K, V) -> bool) -> Result<M, ConversionError>
+where
+    K: TryFrom<Value, Error = ConversionError>,
+    V: TryFrom<Value, Error = ConversionError>,
+{
+    let entries = match value {
+        Value::Map(_, _, entries) => entries,
+        v => return Err(ExpectedFound { expected: "Map", found: v.type_name() }.into()),
+    };
+    for (k, v) in entries {
+        // kept for the error, since two keys are only known to be the same once converted.
+        let key = K::try_from(k.clone())?;
+        if !insert(&mut map, key, V::try_from(v)?) {
+            return Err(ConversionError::DuplicateKey(k));
+        }
+    }
+    Ok(map)
+}
+
+impl<K, V> TryFrom<Value> for BTreeMap<K, V>
+where
+    K: TryFrom<Value, Error = ConversionError> + Ord,
+    V: TryFrom<Value, Error = ConversionError>,
+{
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        collect_map(value, BTreeMap::new(), |map, k, v| map.insert(k, v).is_none())
+    }
+}
+
+impl<K, V, S> TryFrom<Value> for HashMap<K, V, S>
+where
+    K: TryFrom<Value, Error = ConversionError> + Eq + Hash,
+    V: TryFrom<Value, Error = ConversionError>,
+    S: BuildHasher + Default,
+{
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        collect_map(value, HashMap::default(), |map, k, v| map.insert(k, v).is_none())
+    }
+}
+
 pub trait IntoValue {
+    /// The type of the values this converts into.
+    fn value_type() -> ValueType;
     fn into_value(self) -> Value;
 }
 
+impl IntoValue for () {
+    fn value_type() -> ValueType {
+        ValueType::Unit
+    }
+    fn into_value(self) -> Value {
+        Value::Unit
+    }
+}
+
 impl IntoValue for String {
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
     fn into_value(self) -> Value {
         Value::String(self)
     }
 }
 
 impl IntoValue for &'_ str {
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
     fn into_value(self) -> Value {
         Value::String(self.to_owned())
     }
-}
\ No newline at end of file
+}
+
+impl IntoValue for bool {
+    fn value_type() -> ValueType {
+        ValueType::Boolean
+    }
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn value_type() -> ValueType {
+        ValueType::List(Box::new(T::value_type()))
+    }
+    fn into_value(self) -> Value {
+        Value::List(T::value_type(), self.into_iter().map(T::into_value).collect())
+    }
+}
+
+impl<A: IntoValue, B: IntoValue> IntoValue for (A, B) {
+    fn value_type() -> ValueType {
+        ValueType::Pair(Box::new(A::value_type()), Box::new(B::value_type()))
+    }
+    fn into_value(self) -> Value {
+        Value::Pair(Box::new(self.0.into_value()), Box::new(self.1.into_value()))
+    }
+}
+
+impl<K: IntoValue + Ord, V: IntoValue> IntoValue for BTreeMap<K, V> {
+    fn value_type() -> ValueType {
+        ValueType::Map(Box::new(K::value_type()), Box::new(V::value_type()))
+    }
+    fn into_value(self) -> Value {
+        let entries = self.into_iter().map(|(k, v)| (k.into_value(), v.into_value())).collect();
+        Value::Map(K::value_type(), V::value_type(), entries)
+    }
+}
+
+impl<K: IntoValue + Ord, V: IntoValue, S> IntoValue for HashMap<K, V, S> {
+    fn value_type() -> ValueType {
+        ValueType::Map(Box::new(K::value_type()), Box::new(V::value_type()))
+    }
+    /// The entries are sorted by key, so that the result does not depend on the
+    /// iteration order of the map.
+    fn into_value(self) -> Value {
+        let mut entries: Vec<_> = self.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let entries = entries.into_iter().map(|(k, v)| (k.into_value(), v.into_value())).collect();
+        Value::Map(K::value_type(), V::value_type(), entries)
+    }
+}
+
+/// Conversions between numbers of the Rust types and `Value::$variant`, which
+/// holds a number of the arbitrary-precision type `$big`.
+macro_rules! numbers {
+    ($variant:ident($big:ty): $($ty:ty),*) => {$(
+        impl TryFrom<Value> for $ty {
+            type Error = ConversionError;
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$variant(n) => <$ty>::try_from(&n)
+                        .map_err(|_| ConversionError::OutOfRange { value: Value::$variant(n), ty: stringify!($ty) }),
+                    v => Err(ExpectedFound { expected: stringify!($variant), found: v.type_name() }.into()),
+                }
+            }
+        }
+
+        impl IntoValue for $ty {
+            fn value_type() -> ValueType {
+                ValueType::$variant
+            }
+            fn into_value(self) -> Value {
+                Value::$variant(<$big>::from(self))
+            }
+        }
+    )*};
+}
+
+numbers!(Natural(BigUint): u8, u16, u32, u64, u128, usize);
+numbers!(Integer(BigInt): i8, i16, i32, i64, i128, isize);
+
+impl TryFrom<Value> for BigUint {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Natural(n) => Ok(n),
+            v => Err(ExpectedFound { expected: "Natural", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl TryFrom<Value> for BigInt {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(n) => Ok(n),
+            v => Err(ExpectedFound { expected: "Integer", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = ConversionError;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(f) => Ok(f),
+            v => Err(ExpectedFound { expected: "Float", found: v.type_name() }.into()),
+        }
+    }
+}
+
+impl IntoValue for BigUint {
+    fn value_type() -> ValueType {
+        ValueType::Natural
+    }
+    fn into_value(self) -> Value {
+        Value::Natural(self)
+    }
+}
+
+impl IntoValue for BigInt {
+    fn value_type() -> ValueType {
+        ValueType::Integer
+    }
+    fn into_value(self) -> Value {
+        Value::Integer(self)
+    }
+}
+
+impl IntoValue for f64 {
+    fn value_type() -> ValueType {
+        ValueType::Float
+    }
+    fn into_value(self) -> Value {
+        Value::Float(self)
+    }
+}
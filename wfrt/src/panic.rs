@@ -0,0 +1,72 @@
+//! Catching panics in evaluated functions, keeping what the panic hook knows about them.
+
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::RefCell;
+use std::panic::{self, UnwindSafe};
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<Panic>> = const { RefCell::new(None) };
+}
+
+/// A panic in an evaluated function.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Panic {
+    pub message: String,
+    pub location: Option<Location>,
+    /// Only captured when enabled with `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+    pub backtrace: Option<String>,
+}
+
+/// Where a panic happened, in the source generated from the function template.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Location {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
+
+/// Runs `f`, returning the panic if it panics. The panic hook is replaced while
+/// `f` runs, so nothing is printed to stderr.
+pub fn catch_panic<T>(f: impl FnOnce() -> T + UnwindSafe) -> Result<T, Panic> {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(|info| {
+        let backtrace = Backtrace::capture();
+        let panic = Panic {
+            message: payload_message(info.payload()),
+            location: info.location().map(|l| Location {
+                file: l.file().to_owned(),
+                line: l.line(),
+                column: l.column(),
+            }),
+            backtrace: (backtrace.status() == BacktraceStatus::Captured).then(|| backtrace.to_string()),
+        };
+        // when panics abort, as they do on WebAssembly, this is the only chance
+        // to tell the evaluator about the panic.
+        #[cfg(panic = "abort")]
+        if let Ok(json) = serde_json::to_string(&panic) {
+            eprintln!("{json}");
+        }
+        LAST_PANIC.with(|p| *p.borrow_mut() = Some(panic));
+    }));
+    let result = panic::catch_unwind(f);
+    panic::set_hook(previous);
+
+    result.map_err(|payload| {
+        LAST_PANIC.with(|p| p.borrow_mut().take()).unwrap_or_else(|| Panic {
+            message: payload_message(&*payload),
+            location: None,
+            backtrace: None,
+        })
+    })
+}
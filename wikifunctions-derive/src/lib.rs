@@ -0,0 +1,357 @@
+//! `#[derive(ZObject)]` for the `wikifunctions` crate.
+//!
+//! ```ignore
+//! #[derive(Debug, ZObject)]
+//! #[zobject(Z60)]
+//! pub struct NaturalLanguage {
+//!     #[zobject(K1)]
+//!     pub code: ZString,
+//!     #[zobject(K2)]
+//!     pub code_aliases: TypedList<ZString>,
+//! }
+//! ```
+//!
+//! This implements `ZObject` with the type `Z60`, and `Serialize` and `Deserialize`
+//! with the fields at the keys `Z60K1` and `Z60K2`. Every field is (de)serialized
+//! as an `Object`, so it must be a `ZObject` itself. A field can also be given a
+//! full key with `#[zobject(rename = "Z60K1")]`.
+//!
+//! Keys that may be left out of an object are marked with `#[zobject(K4, optional)]`
+//! on a field of type `Option<T>`. They are `None` when missing, and are not written
+//! when `None`.
+//!
+//! Types whose type is not a reference, such as the Z882/typed pair which is typed
+//! by a function call, give it with `#[zobject(Z882, ztype = ...)]`. The ZID is
+//! then only used for keys like `K1`, which can be renamed as above.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericArgument,
+    GenericParam, Ident, LitStr, PathArguments, Result, Type, WherePredicate,
+};
+
+#[proc_macro_derive(ZObject, attributes(zobject))]
+pub fn derive_zobject(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    ident: Ident,
+    /// The type of the value, which is `T` for an optional `Option<T>` field.
+    ty: Type,
+    key: String,
+    optional: bool,
+}
+
+/// Parses a ZID such as `Z60`, returning its number.
+fn parse_zid(ident: &Ident) -> Option<u32> {
+    let s = ident.to_string();
+    let digits = s.strip_prefix('Z')?;
+    if digits.starts_with('0') {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Parses the ZID of the type, and the type of the type if it is given with `ztype`.
+fn type_id(input: &DeriveInput) -> Result<(u32, Option<Type>)> {
+    let mut id = None;
+    let mut ztype = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("zobject")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("ztype") {
+                if ztype.is_some() {
+                    return Err(meta.error("the type of the type can only be given once"));
+                }
+                ztype = Some(meta.value()?.parse::<Type>()?);
+                return Ok(());
+            }
+            let zid = meta.path.get_ident().and_then(parse_zid);
+            match zid {
+                Some(zid) if id.is_none() => {
+                    id = Some(zid);
+                    Ok(())
+                }
+                Some(_) => Err(meta.error("the type of a ZObject can only be given once")),
+                None => {
+                    Err(meta.error("expected the ZID of the type, like `Z60`, or `ztype = ...`"))
+                }
+            }
+        })?;
+    }
+    let id = id.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "expected the ZID of the type in a `#[zobject(...)]` attribute, like `#[zobject(Z60)]`",
+        )
+    })?;
+    Ok((id, ztype))
+}
+
+/// Returns `T` if `ty` is an `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if segment.ident == "Option" && args.args.len() == 1 => {
+            Some(inner)
+        }
+        _ => None,
+    }
+}
+
+fn parse_field(type_id: u32, field: &syn::Field) -> Result<Field> {
+    let mut key = None;
+    let mut optional = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("zobject")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("optional") {
+                optional = true;
+                return Ok(());
+            }
+            if key.is_some() {
+                return Err(meta.error("the key of a field can only be given once"));
+            }
+            if meta.path.is_ident("rename") {
+                key = Some(meta.value()?.parse::<LitStr>()?.value());
+                return Ok(());
+            }
+            let number = meta
+                .path
+                .get_ident()
+                .and_then(|ident| ident.to_string().strip_prefix('K')?.parse::<u32>().ok());
+            match number {
+                Some(n) => {
+                    key = Some(format!("Z{type_id}K{n}"));
+                    Ok(())
+                }
+                None => {
+                    Err(meta.error("expected a key like `K1`, `rename = \"...\"`, or `optional`"))
+                }
+            }
+        })?;
+    }
+    let key = key.ok_or_else(|| {
+        Error::new_spanned(
+            field,
+            "expected the key of the field in a `#[zobject(...)]` attribute, like `#[zobject(K1)]`",
+        )
+    })?;
+    let ty = if optional {
+        option_inner(&field.ty)
+            .ok_or_else(|| Error::new_spanned(&field.ty, "an optional field must be an `Option`"))?
+    } else {
+        &field.ty
+    };
+    Ok(Field {
+        ident: field.ident.clone().unwrap(),
+        ty: ty.clone(),
+        key,
+        optional,
+    })
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let (id, ztype) = type_id(&input)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`ZObject` can only be derived for structs",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`ZObject` can only be derived for structs with named fields",
+        ));
+    };
+    let fields = named
+        .named
+        .iter()
+        .map(|f| parse_field(id, f))
+        .collect::<Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let ztype = ztype.unwrap_or_else(|| {
+        parse_quote! {
+            ::wikifunctions::model::Object<
+                ::wikifunctions::model::Reference<::wikifunctions::label::Z<#id>>,
+            >
+        }
+    });
+    let zobject = quote! {
+        impl #impl_generics ::wikifunctions::model::ZObject for #name #ty_generics #where_clause {
+            type ZType = #ztype;
+        }
+    };
+
+    let field_tys = fields.iter().map(|f| &f.ty);
+    let field_idents: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+    let keys: Vec<_> = fields.iter().map(|f| &f.key).collect();
+    let required_len = fields.iter().filter(|f| !f.optional).count();
+    let optional_idents = fields.iter().filter(|f| f.optional).map(|f| &f.ident);
+    let len = quote!(#required_len #(+ usize::from(self.#optional_idents.is_some()))*);
+    let serialize_fields = fields.iter().map(|f| {
+        let (ident, key) = (&f.ident, &f.key);
+        let serialize_field = |value| {
+            quote! {
+                __serde::ser::SerializeStruct::serialize_field(
+                    &mut __s,
+                    #key,
+                    &::wikifunctions::model::Object::new(#value),
+                )?;
+            }
+        };
+        if f.optional {
+            let serialize_field = serialize_field(quote!(__value));
+            quote! {
+                match &self.#ident {
+                    ::core::option::Option::Some(__value) => { #serialize_field }
+                    ::core::option::Option::None => {
+                        __serde::ser::SerializeStruct::skip_field(&mut __s, #key)?;
+                    }
+                }
+            }
+        } else {
+            serialize_field(quote!(&self.#ident))
+        }
+    });
+
+    let mut ser_generics = input.generics.clone();
+    ser_generics
+        .make_where_clause()
+        .predicates
+        .extend(field_tys.clone().map(|ty| -> WherePredicate {
+            parse_quote!(#ty: ::wikifunctions::model::ZObject + __serde::Serialize)
+        }));
+    let (_, _, ser_where_clause) = ser_generics.split_for_impl();
+
+    let serialize = quote! {
+        impl #impl_generics __serde::Serialize for #name #ty_generics #ser_where_clause {
+            fn serialize<__S>(&self, __serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+            where
+                __S: __serde::Serializer,
+            {
+                let mut __s = __serde::Serializer::serialize_struct(__serializer, #name_str, #len)?;
+                #(#serialize_fields)*
+                __serde::ser::SerializeStruct::end(__s)
+            }
+        }
+    };
+
+    let mut de_generics = input.generics.clone();
+    let lifetimes: Vec<_> = de_generics
+        .lifetimes()
+        .map(|l| l.lifetime.clone())
+        .collect();
+    de_generics.params.insert(0, parse_quote!('de));
+    if let Some(GenericParam::Lifetime(de)) = de_generics.params.first_mut() {
+        de.bounds.extend(lifetimes);
+    }
+    de_generics
+        .make_where_clause()
+        .predicates
+        .extend(field_tys.map(|ty| -> WherePredicate {
+            parse_quote!(#ty: ::wikifunctions::model::ZObject + __serde::Deserialize<'de>)
+        }));
+    let (de_impl_generics, visitor_ty_generics, de_where_clause) = de_generics.split_for_impl();
+
+    let vars: Vec<_> = (0..fields.len()).map(|i| format_ident!("__f{i}")).collect();
+    let tys = fields.iter().map(|f| &f.ty);
+    let unwrap_fields = fields
+        .iter()
+        .zip(&vars)
+        .filter(|(f, _)| !f.optional)
+        .map(|(f, var)| {
+            let key = &f.key;
+            quote! {
+                let #var = #var.ok_or_else(|| {
+                    <__A::Error as __serde::de::Error>::missing_field(#key)
+                })?;
+            }
+        });
+    let expecting = format!("a Z{id}");
+
+    let deserialize = quote! {
+        impl #de_impl_generics __serde::Deserialize<'de> for #name #ty_generics #de_where_clause {
+            fn deserialize<__D>(__deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+            where
+                __D: __serde::Deserializer<'de>,
+            {
+                struct __Visitor #de_impl_generics (
+                    ::core::marker::PhantomData<(fn() -> #name #ty_generics, &'de ())>,
+                ) #de_where_clause;
+
+                impl #de_impl_generics __serde::de::Visitor<'de> for __Visitor #visitor_ty_generics #de_where_clause {
+                    type Value = #name #ty_generics;
+
+                    fn expecting(&self, __f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        __f.pad(#expecting)
+                    }
+
+                    fn visit_map<__A>(self, mut __map: __A) -> ::core::result::Result<Self::Value, __A::Error>
+                    where
+                        __A: __serde::de::MapAccess<'de>,
+                    {
+                        #(let mut #vars: ::core::option::Option<#tys> = ::core::option::Option::None;)*
+                        while let ::core::option::Option::Some(__key) =
+                            __serde::de::MapAccess::next_key::<::wikifunctions::__private::Key<'de>>(&mut __map)?
+                        {
+                            match &*__key {
+                                #(
+                                    #keys => {
+                                        if #vars.is_some() {
+                                            return ::core::result::Result::Err(
+                                                <__A::Error as __serde::de::Error>::duplicate_field(#keys),
+                                            );
+                                        }
+                                        #vars = ::core::option::Option::Some(
+                                            __serde::de::MapAccess::next_value::<::wikifunctions::model::Object<_>>(&mut __map)?.value,
+                                        );
+                                    }
+                                )*
+                                __other => {
+                                    return ::core::result::Result::Err(
+                                        <__A::Error as __serde::de::Error>::unknown_field(__other, &[#(#keys),*]),
+                                    );
+                                }
+                            }
+                        }
+                        #(#unwrap_fields)*
+                        ::core::result::Result::Ok(#name { #(#field_idents: #vars),* })
+                    }
+                }
+
+                __serde::Deserializer::deserialize_map(
+                    __deserializer,
+                    __Visitor(::core::marker::PhantomData),
+                )
+            }
+        }
+    };
+
+    Ok(quote! {
+        #zobject
+
+        const _: () = {
+            use ::wikifunctions::__private::serde as __serde;
+
+            #serialize
+
+            #deserialize
+        };
+    })
+}